@@ -0,0 +1,110 @@
+//! Benchmarks for rendering templates with the fluent helpers.
+//!
+//! Requires a nightly toolchain:
+//!
+//! ```text
+//! cargo +nightly bench --features nightly
+//! ```
+#![feature(test)]
+extern crate test;
+
+use std::sync::Arc;
+
+use bracket::Registry;
+use serde_json::json;
+use test::Bencher;
+use unic_langid::langid;
+
+use bracket_fluent::{Catalog, FluentHelper, FluentHelperBuilder};
+
+static LOCALES: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/locales");
+
+/// Number of helper calls in a template.
+const CALLS: usize = 100;
+
+fn catalog() -> Arc<Catalog> {
+    let shared = [format!("{}/core.ftl", LOCALES).into()];
+    let catalog = Catalog::builder(LOCALES, langid!("en"))
+        .shared_resources(Some(&shared))
+        .build()
+        .unwrap();
+    Arc::new(catalog)
+}
+
+fn builder() -> FluentHelperBuilder {
+    let catalog = catalog();
+    FluentHelper::builder(Box::new(Arc::clone(&catalog))).catalog(catalog)
+}
+
+/// Render a template that repeats a helper call.
+fn render(b: &mut Bencher, helper: FluentHelper, call: &str) {
+    render_template(b, helper, call.repeat(CALLS));
+}
+
+/// Render a template with the helpers registered.
+fn render_template(b: &mut Bencher, helper: FluentHelper, template: String) {
+    let mut registry = Registry::new();
+    bracket_fluent::register_all(&mut registry, helper);
+    registry.insert("bench", template).unwrap();
+    let data = json!({"lang": "fr"});
+    b.iter(|| registry.render("bench", &data).unwrap());
+}
+
+#[bench]
+fn lang(b: &mut Bencher) {
+    render(b, builder().build(), r#"{{fluent "welcome"}}"#);
+}
+
+#[bench]
+fn many_args(b: &mut Bencher) {
+    let args: String = (0..12).map(|i| format!(" arg{}={}", i, i)).collect();
+    let call = format!(r#"{{{{fluent "greeting" name="Ann"{}}}}}"#, args);
+    render(b, builder().build(), &call);
+}
+
+#[bench]
+fn block_params(b: &mut Bencher) {
+    let call = r#"{{#fluent "greeting"}}{{#fluentparam "name"}}Ann{{/fluentparam}}{{/fluent}}"#;
+    render(b, builder().build(), call);
+}
+
+#[bench]
+fn uncached(b: &mut Bencher) {
+    render(b, builder().build(), r#"{{fluent "greeting" name="Ann"}}"#);
+}
+
+#[bench]
+fn cached(b: &mut Bencher) {
+    let helper = builder().cache_capacity(128).build();
+    render(b, helper, r#"{{fluent "greeting" name="Ann"}}"#);
+}
+
+#[bench]
+fn pinned_lang(b: &mut Bencher) {
+    let calls = r#"{{fluent "welcome"}}"#.repeat(CALLS);
+    let template = format!("{{{{#fluent_pin}}}}{}{{{{/fluent_pin}}}}", calls);
+    render_template(b, builder().build(), template);
+}
+
+#[bench]
+fn has_fluent(b: &mut Bencher) {
+    let call = r#"{{#if (has_fluent "greeting")}}yes{{/if}}"#;
+    render(b, builder().build(), call);
+}
+
+#[bench]
+fn format_to_check(b: &mut Bencher) {
+    let call = r#"{{#if (fluent "greeting" name="Ann")}}yes{{/if}}"#;
+    render(b, builder().build(), call);
+}
+
+#[bench]
+fn plain_messages(b: &mut Bencher) {
+    render(b, builder().build(), r#"{{fluent "welcome"}}"#);
+}
+
+#[bench]
+fn escaped_messages(b: &mut Bencher) {
+    render(b, builder().build(), r#"{{fluent "raw-html"}}"#);
+}
@@ -28,10 +28,14 @@ fn render() -> Result<String> {
         //"lang": "fr",
     });
 
+    // The static loader cannot be customized so remove the
+    // isolation marks using the helper
+    let helper = FluentHelper::builder(Box::new(&*LOCALES))
+        .isolating(false)
+        .build();
+
     let mut registry = Registry::new();
-    registry
-        .helpers_mut()
-        .insert("fluent", Box::new(FluentHelper::new(Box::new(&*LOCALES))));
+    registry.helpers_mut().insert("fluent", Box::new(helper));
 
     registry.load(PathBuf::from(name))?;
     registry.render(name, &data)
@@ -0,0 +1,570 @@
+//! Convert hash parameters to fluent arguments.
+use std::collections::HashMap;
+
+use bracket::{
+    error::HelperError,
+    helper::HelperResult,
+    render::{Context, Type},
+};
+
+use serde_json::{Map, Number, Value};
+
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::LanguageIdentifier;
+
+use crate::{lang::LANG, number};
+
+pub(crate) static ESCAPE: &str = "escape";
+pub(crate) static DEFAULT: &str = "default";
+pub(crate) static ARGS: &str = "args";
+pub(crate) static COUNT: &str = "count";
+pub(crate) static INLINE: &str = "inline";
+pub(crate) static CAPTURE_BLOCK: &str = "capture_block";
+pub(crate) static ON_MISSING: &str = "on_missing";
+pub(crate) static LIST_SEPARATOR: &str = ", ";
+pub(crate) static FLATTEN_SEPARATOR: &str = "-";
+
+/// Largest integer that can be represented exactly by an `f64`.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Get a string argument.
+///
+/// Returns an error rather than panicking when the
+/// argument is not a string.
+pub(crate) fn string_arg<'a>(
+    ctx: &'a Context<'_>,
+    index: usize,
+) -> HelperResult<&'a str> {
+    ctx.try_get(index, &[Type::String])?
+        .as_str()
+        .ok_or_else(|| {
+            HelperError::new(format!(
+                "Type error in helper '{}' argument {} must be a string",
+                ctx.name(),
+                index
+            ))
+        })
+}
+
+/// Get a message id argument.
+///
+/// Message ids may be computed from the template data, for example
+/// `{{fluent (lookup items "key")}}`, so the error for a value that
+/// is not a string includes the value that was resolved.
+pub(crate) fn msg_id_arg<'a>(
+    ctx: &'a Context<'_>,
+    index: usize,
+) -> HelperResult<&'a str> {
+    match ctx.get(index) {
+        Some(Value::String(msg_id)) => Ok(msg_id),
+        Some(Value::Null) => Err(HelperError::new(format!(
+            "Type error in helper '{}' the message id (argument {}) must be a string but got null, the value may be missing from the template data",
+            ctx.name(),
+            index
+        ))),
+        Some(value) => Err(HelperError::new(format!(
+            "Type error in helper '{}' the message id (argument {}) must be a string but got {} '{}'",
+            ctx.name(),
+            index,
+            Type::from(value),
+            value
+        ))),
+        None => string_arg(ctx, index),
+    }
+}
+
+/// Determine if a hash parameter is used by the helpers
+/// and should not be passed to the message.
+pub(crate) fn is_reserved(key: &str) -> bool {
+    key == LANG
+        || key == ESCAPE
+        || key == DEFAULT
+        || key == ARGS
+        || key == INLINE
+        || key == CAPTURE_BLOCK
+        || key == ON_MISSING
+}
+
+/// Type for functions that convert hash parameters to fluent values.
+///
+/// The function receives the argument name and the JSON value and
+/// is called before the default conversion; return `None` to use
+/// the default conversion.
+pub type ConvertFn =
+    Box<dyn Fn(&str, &Value) -> Option<FluentValue<'static>> + Send + Sync>;
+
+/// Type for functions that transform the arguments for a language.
+///
+/// The function receives the language resolved for the call and the
+/// arguments, including block parameters, before the message is
+/// resolved so it can rewrite arguments for a locale, for example to
+/// change the order of names.
+pub type ArgsTransformFn = Box<
+    dyn Fn(&LanguageIdentifier, &mut HashMap<String, FluentValue<'static>>)
+        + Send
+        + Sync,
+>;
+
+/// Options for converting JSON values to fluent arguments.
+pub(crate) struct Conversion<'a> {
+    /// Function called before the default conversion.
+    pub convert: Option<&'a ConvertFn>,
+    /// Separator used to join array values.
+    pub list_separator: &'a str,
+    /// Levels of nested objects to flatten into arguments.
+    pub flatten_depth: usize,
+    /// Separator used to join the keys of flattened objects.
+    pub flatten_separator: &'a str,
+}
+
+impl<'a> Conversion<'a> {
+    /// Convert a value and insert it into the arguments.
+    ///
+    /// Objects are flattened when the flatten depth is set so that
+    /// each member is inserted using the key joined with the member
+    /// name; arrays and objects nested deeper are skipped.
+    ///
+    /// The conversion function is called first for every value.
+    ///
+    /// A numeric string assigned to `count` is passed as a number
+    /// unless it is not finite (`NaN` or `inf`) so that it can be used for plural selection and a tagged number
+    /// returned by a `fluent_number` sub-expression is passed as a
+    /// number with the formatting options.
+    pub(crate) fn insert(
+        &self,
+        args: &mut HashMap<String, FluentValue<'static>>,
+        key: &str,
+        value: &Value,
+    ) {
+        if let Some(value) =
+            self.convert.and_then(|convert| convert(key, value))
+        {
+            args.insert(key.to_string(), value);
+            return;
+        }
+
+        if let (true, Value::String(s)) = (key == COUNT, value) {
+            if let Some(n) =
+                s.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+            {
+                args.insert(key.to_string(), n.into());
+                return;
+            }
+        }
+
+        if let Some(value) = number::from_tagged(value) {
+            args.insert(key.to_string(), value);
+        } else if let Value::Object(fields) = value {
+            self.flatten(args, key, fields, self.flatten_depth);
+        } else if let Some(value) =
+            to_fluent_value(key, value, self.list_separator)
+        {
+            args.insert(key.to_string(), value);
+        }
+    }
+
+    fn flatten(
+        &self,
+        args: &mut HashMap<String, FluentValue<'static>>,
+        key: &str,
+        fields: &Map<String, Value>,
+        depth: usize,
+    ) {
+        if depth == 0 {
+            return;
+        }
+        for (name, value) in fields {
+            let key = format!("{}{}{}", key, self.flatten_separator, name);
+            match value {
+                Value::Object(fields) => {
+                    self.flatten(args, &key, fields, depth - 1)
+                }
+                Value::Array(_) => {}
+                _ => self.insert(args, &key, value),
+            }
+        }
+    }
+}
+
+/// Build the fluent arguments from the hash parameters.
+///
+/// The members of an object assigned to the `args` hash parameter
+/// are also passed as arguments; other hash parameters take precedence
+/// over members with the same name.
+///
+/// Returns `None` when there are no hash parameters.
+pub(crate) fn from_parameters(
+    ctx: &Context<'_>,
+    conversion: &Conversion<'_>,
+) -> Option<HashMap<String, FluentValue<'static>>> {
+    if ctx.parameters().is_empty() {
+        None
+    } else {
+        let mut map = HashMap::with_capacity(ctx.parameters().len());
+        match ctx.param(ARGS) {
+            Some(Value::Object(members)) => {
+                map.reserve(members.len());
+                for (k, v) in members {
+                    conversion.insert(&mut map, k, v);
+                }
+            }
+            Some(_) => {
+                log::warn!(
+                    "Skipping parameter '{}' of helper '{}', expected an object",
+                    ARGS,
+                    ctx.name()
+                );
+            }
+            None => {}
+        }
+        for (k, v) in ctx.parameters() {
+            if is_reserved(k) {
+                continue;
+            }
+            conversion.insert(&mut map, k, v);
+        }
+        Some(map)
+    }
+}
+
+/// Merge the scalar fields of the root data into the arguments.
+///
+/// Hash parameters take precedence so fields are only added
+/// when the call does not have a hash parameter or `args` member
+/// with the same name; a `null` hash parameter therefore removes
+/// the field.
+pub(crate) fn inherit(
+    args: &mut HashMap<String, FluentValue<'static>>,
+    ctx: &Context<'_>,
+    root: &Value,
+) {
+    if let Value::Object(fields) = root {
+        for (k, v) in fields {
+            if args.contains_key(k) || ctx.parameters().contains_key(k) {
+                continue;
+            }
+            let value = match v {
+                Value::Number(n) => number_to_fluent_value(n),
+                Value::String(s) => s.to_owned().into(),
+                Value::Bool(b) => b.to_string().into(),
+                _ => continue,
+            };
+            args.insert(k.to_string(), value);
+        }
+    }
+}
+
+/// Determine if a hash parameter is `null` which removes an
+/// argument that would otherwise be inherited or defaulted.
+fn is_unset(ctx: &Context<'_>, key: &str) -> bool {
+    matches!(ctx.param(key), Some(Value::Null))
+}
+
+/// Merge default arguments into the arguments.
+///
+/// Defaults are only added when there is no argument with
+/// the same name and the call does not set a hash parameter
+/// with the same name to `null`.
+pub(crate) fn merge_defaults(
+    args: &mut HashMap<String, FluentValue<'static>>,
+    ctx: &Context<'_>,
+    defaults: &HashMap<String, Value>,
+    conversion: &Conversion<'_>,
+) {
+    let mut values = HashMap::new();
+    for (k, v) in defaults {
+        conversion.insert(&mut values, k, v);
+    }
+    for (k, v) in values {
+        if !is_unset(ctx, &k) {
+            args.entry(k).or_insert(v);
+        }
+    }
+}
+
+/// Convert a JSON value to a fluent value.
+///
+/// Arrays are joined into a single string using the list
+/// separator; elements that cannot be converted to a string
+/// are skipped. Other non-scalar values are ignored.
+pub(crate) fn to_fluent_value(
+    name: &str,
+    value: &Value,
+    list_separator: &str,
+) -> Option<FluentValue<'static>> {
+    match value {
+        Value::Number(n) => Some(number_to_fluent_value(n)),
+        Value::String(s) => Some(s.to_owned().into()),
+        // Fluent has no boolean type so we pass the
+        // string `true` or `false`.
+        Value::Bool(b) => Some(b.to_string().into()),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| match item {
+                    Value::String(s) => Some(s.to_owned()),
+                    Value::Number(n) => Some(n.to_string()),
+                    Value::Bool(b) => Some(b.to_string()),
+                    _ => {
+                        log::warn!(
+                            "Skipping element {} of parameter '{}', \
+                            expected a string, number or boolean",
+                            i,
+                            name
+                        );
+                        None
+                    }
+                })
+                .collect();
+            Some(items.join(list_separator).into())
+        }
+        _ => None,
+    }
+}
+
+/// Convert a JSON number to a fluent value.
+///
+/// Fluent numbers are backed by `f64` so integers that cannot be
+/// represented exactly and numbers outside the range of an `f64`
+/// are passed as strings to preserve every digit.
+fn number_to_fluent_value(n: &Number) -> FluentValue<'static> {
+    if let Some(i) = n.as_i64() {
+        if i.unsigned_abs() <= MAX_SAFE_INTEGER {
+            i.into()
+        } else {
+            i.to_string().into()
+        }
+    } else if let Some(u) = n.as_u64() {
+        if u <= MAX_SAFE_INTEGER {
+            u.into()
+        } else {
+            u.to_string().into()
+        }
+    } else {
+        // When the `arbitrary_precision` feature of `serde_json` is
+        // enabled the number may not fit in an `f64` so we parse the
+        // string representation and otherwise pass the string.
+        let value = n.as_f64().or_else(|| n.to_string().parse::<f64>().ok());
+        match value.filter(|value| value.is_finite()) {
+            Some(value) => value.into(),
+            None => n.to_string().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Number, Value};
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_util::{catalog, fluent, loader};
+    use crate::FluentHelper;
+
+    fn render(template: &str) -> String {
+        let helper = FluentHelper::new(Box::new(loader()));
+        fluent(helper, template, json!({"lang": "en"})).unwrap()
+    }
+
+    #[test]
+    fn booleans() {
+        let template = r#"{{fluent "status" active=true}} {{fluent "status" active=false}}"#;
+        assert_eq!(render(template), "Active Inactive");
+    }
+
+    #[test]
+    fn arrays() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let data = json!({"lang": "en", "list": ["a", "b", {}, 1]});
+        let result =
+            fluent(helper, r#"{{fluent "list-items" items=list}}"#, data);
+        assert_eq!(result.unwrap(), "Items: a, b, 1");
+    }
+
+    #[test]
+    fn large_integers() {
+        let template = r#"{{fluent "echo" id=9007199254740993}} {{fluent "echo" id=-42}} {{fluent "echo" id=1.5}}"#;
+        assert_eq!(render(template), "9007199254740993 -42 1.5");
+    }
+
+    #[test]
+    fn inherit_root_args() {
+        let template =
+            r#"{{fluent "greeting"}} {{fluent "greeting" name="Bob"}}"#;
+        let mut helper = FluentHelper::new(Box::new(loader()));
+        helper.inherit_root_args = true;
+        let result =
+            fluent(helper, template, json!({"lang": "en", "name": "Ann"}));
+        assert_eq!(result.unwrap(), "Hello Ann! Hello Bob!");
+    }
+
+    #[test]
+    fn args_object() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let data = json!({"lang": "en", "order": {"name": "Ann", "count": 2, "currency": "USD"}});
+        let template = r#"{{fluent "receipt" args=order currency="EUR"}}"#;
+        assert_eq!(fluent(helper, template, data).unwrap(), "Ann bought 2 EUR");
+    }
+
+    #[test]
+    fn default_args() {
+        let mut defaults = HashMap::new();
+        defaults.insert("name".to_string(), json!("Ann"));
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .default_args(defaults)
+            .build();
+        let template =
+            r#"{{fluent "greeting"}} {{fluent "greeting" name="Bob"}}"#;
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann! Hello Bob!");
+    }
+
+    #[test]
+    fn flatten() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .flatten_depth(1)
+            .build();
+        let data = json!({"lang": "en", "user": {"name": "Ann", "city": "Oslo", "tags": ["a"]}});
+        let result = fluent(helper, r#"{{fluent "addr" user=user}}"#, data);
+        assert_eq!(result.unwrap(), "Ann, Oslo");
+    }
+
+    #[test]
+    fn count_string() {
+        let template =
+            r#"{{fluent "items" count="1"}} {{fluent "items" count=2}}"#;
+        assert_eq!(render(template), "One item 2 items");
+    }
+
+    #[test]
+    fn count_not_finite() {
+        let conversion = Conversion {
+            convert: None,
+            list_separator: ", ",
+            flatten_depth: 0,
+            flatten_separator: "-",
+        };
+        for count in &["NaN", " inf", "-infinity"] {
+            let mut args = HashMap::new();
+            conversion.insert(&mut args, COUNT, &json!(count));
+            assert_eq!(args[COUNT], FluentValue::from(count.to_string()));
+        }
+        let template = r#"{{fluent "items" count="NaN"}}"#;
+        assert_eq!(render(template), "NaN items");
+    }
+
+    #[test]
+    fn null_unsets_argument() {
+        let mut defaults = HashMap::new();
+        defaults.insert("name".to_string(), json!("Guest"));
+        defaults.insert("count".to_string(), json!(3));
+        // The catalog logs the missing variable instead of panicking
+        let helper = FluentHelper::builder(Box::new(catalog()))
+            .default_args(defaults)
+            .inherit_root_args(true)
+            .build();
+        let template = r#"{{fluent "greeting"}} {{fluent "greeting" name=null}} {{fluent "items"}} {{fluent "items" count=null}}"#;
+        let result =
+            fluent(helper, template, json!({"lang": "en", "name": "Ann"}));
+        assert_eq!(
+            result.unwrap(),
+            "Hello Ann! Hello {$name}! 3 items {$count} items"
+        );
+    }
+
+    #[test]
+    fn convert() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .convert(Box::new(|name, value| match (name, value) {
+                ("name", Value::String(s)) => Some(s.to_uppercase().into()),
+                _ => None,
+            }))
+            .build();
+        let template =
+            r#"{{fluent "receipt" name="ann" count=2 currency="eur"}}"#;
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "ANN bought 2 eur");
+    }
+
+    #[test]
+    fn message_id_type() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let data = json!({"lang": "en", "id": 42});
+        let err = fluent(helper, r#"{{fluent id}}"#, data).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("must be a string but got number '42'"));
+
+        let helper = FluentHelper::new(Box::new(loader()));
+        let err =
+            fluent(helper, r#"{{fluent missing}}"#, json!({"lang": "en"}))
+                .unwrap_err();
+        assert!(err.to_string().contains("must be a string but got null"));
+    }
+
+    #[test]
+    fn high_precision() {
+        let value = number_to_fluent_value(&u64::MAX.into());
+        assert!(
+            matches!(value, FluentValue::String(s) if s == "18446744073709551615")
+        );
+        let value = number_to_fluent_value(&i64::MIN.into());
+        assert!(
+            matches!(value, FluentValue::String(s) if s == "-9223372036854775808")
+        );
+        let n: Number =
+            serde_json::from_str("0.1234567890123456789012345678901").unwrap();
+        let value = number_to_fluent_value(&n);
+        assert!(
+            matches!(value, FluentValue::Number(n) if n.value == 0.12345678901234568)
+        );
+    }
+
+    #[test]
+    fn hyphenated_keys() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let data = json!({"lang": "en", "user": {"user-name": "Ann", "user-city": "Oslo"}});
+        let result = fluent(helper, r#"{{fluent "addr" args=user}}"#, data);
+        assert_eq!(result.unwrap(), "Ann, Oslo");
+    }
+
+    #[test]
+    fn computed_id() {
+        let data =
+            json!({"lang": "en", "page": {"title": "welcome", "id": 1.5}});
+        let helper = FluentHelper::new(Box::new(loader()));
+        let result = fluent(helper, r#"{{fluent page.title}}"#, data.clone());
+        assert_eq!(result.unwrap(), "Hello!");
+
+        let helper = FluentHelper::new(Box::new(loader()));
+        let err = fluent(helper, r#"{{fluent page.id}}"#, data).unwrap_err();
+        assert!(err.to_string().contains(
+            "the message id (argument 0) must be a string but got number '1.5'"
+        ));
+    }
+
+    #[test]
+    fn args_transform() {
+        let transform = || {
+            FluentHelper::builder(Box::new(loader()))
+                .args_transform(Box::new(|lang_id, args| {
+                    if lang_id.language == "de" {
+                        let name = args.remove("user-name");
+                        let city = args.remove("user-city");
+                        if let (Some(name), Some(city)) = (name, city) {
+                            args.insert("user-name".to_string(), city);
+                            args.insert("user-city".to_string(), name);
+                        }
+                    }
+                }))
+                .build()
+        };
+        let template = r#"{{fluent "addr" args=user}}"#;
+        let user = json!({"user-name": "Ann", "user-city": "Oslo"});
+        let data = json!({"lang": "en", "user": user});
+        assert_eq!(fluent(transform(), template, data).unwrap(), "Ann, Oslo");
+        let data = json!({"lang": "de", "user": user});
+        assert_eq!(fluent(transform(), template, data).unwrap(), "Oslo, Ann");
+    }
+}
@@ -0,0 +1,234 @@
+//! Bounded cache of formatted messages.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::LanguageIdentifier;
+
+/// Argument value stored in a key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Arg {
+    String(String),
+    Number(u64, String),
+    None,
+}
+
+/// Key for a formatted message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    lang: LanguageIdentifier,
+    msg_id: String,
+    args: Vec<(String, Arg)>,
+}
+
+/// Cached message and the tick when it was last used.
+struct Entry {
+    message: String,
+    used: u64,
+}
+
+struct Inner {
+    entries: HashMap<Key, Entry>,
+    /// Keys ordered by the tick when they were last used.
+    order: BTreeMap<u64, Key>,
+    tick: u64,
+    generation: usize,
+}
+
+impl Inner {
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Cache of formatted messages keyed by language, message id
+/// and arguments.
+///
+/// When the cache is full the least recently used message is evicted.
+pub(crate) struct MessageCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl MessageCache {
+    /// Create a cache that holds up to `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner {
+                entries: HashMap::with_capacity(capacity),
+                order: BTreeMap::new(),
+                tick: 0,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Get a cached message or format and cache it.
+    ///
+    /// Arguments with custom values cannot be compared so
+    /// those messages are always formatted. When the generation
+    /// of the loader changes the cached messages are discarded.
+    pub fn get_or_insert<F>(
+        &self,
+        generation: usize,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+        args: Option<&HashMap<String, FluentValue<'static>>>,
+        format: F,
+    ) -> String
+    where
+        F: FnOnce() -> String,
+    {
+        if self.capacity == 0 {
+            return format();
+        }
+
+        let args = match key_args(args) {
+            Some(args) => args,
+            None => return format(),
+        };
+        let key = Key {
+            lang: lang.clone(),
+            msg_id: msg_id.to_string(),
+            args,
+        };
+
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.generation != generation {
+                inner.clear();
+                inner.generation = generation;
+            }
+            inner.tick += 1;
+            let tick = inner.tick;
+            let inner = &mut *inner;
+            if let Some(entry) = inner.entries.get_mut(&key) {
+                inner.order.remove(&entry.used);
+                inner.order.insert(tick, key);
+                entry.used = tick;
+                return entry.message.clone();
+            }
+        }
+
+        // Format without holding the lock
+        let message = format();
+
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.generation != generation
+                || inner.entries.contains_key(&key)
+            {
+                return message;
+            }
+            if inner.entries.len() >= self.capacity {
+                let oldest = inner.order.keys().next().copied();
+                if let Some(oldest) = oldest {
+                    if let Some(key) = inner.order.remove(&oldest) {
+                        inner.entries.remove(&key);
+                    }
+                }
+            }
+            inner.tick += 1;
+            let used = inner.tick;
+            inner.order.insert(used, key.clone());
+            inner.entries.insert(
+                key,
+                Entry {
+                    message: message.clone(),
+                    used,
+                },
+            );
+        }
+
+        message
+    }
+
+    /// Remove all cached messages.
+    pub fn clear(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.clear();
+        }
+    }
+}
+
+/// Collect the arguments sorted by name.
+fn key_args(
+    args: Option<&HashMap<String, FluentValue<'static>>>,
+) -> Option<Vec<(String, Arg)>> {
+    let mut list = Vec::new();
+    if let Some(args) = args {
+        for (name, value) in args {
+            let value = match value {
+                FluentValue::String(value) => Arg::String(value.to_string()),
+                FluentValue::Number(number) => Arg::Number(
+                    number.value.to_bits(),
+                    format!("{:?}", number.options),
+                ),
+                FluentValue::None => Arg::None,
+                _ => return None,
+            };
+            list.push((name.clone(), value));
+        }
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    Some(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use unic_langid::langid;
+
+    use super::*;
+
+    fn args(
+        values: &[(&str, &'static str)],
+    ) -> HashMap<String, FluentValue<'static>> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), FluentValue::from(*value)))
+            .collect()
+    }
+
+    #[test]
+    fn arguments() {
+        let cache = MessageCache::new(8);
+        let lang = langid!("en");
+        let ann = args(&[("name", "Ann"), ("title", "Dr")]);
+        let bob = args(&[("name", "Bob"), ("title", "Dr")]);
+        let first = cache
+            .get_or_insert(0, &lang, "msg", Some(&ann), || "Ann".to_string());
+        let second = cache
+            .get_or_insert(0, &lang, "msg", Some(&bob), || "Bob".to_string());
+        let hit =
+            cache.get_or_insert(0, &lang, "msg", Some(&ann), || unreachable!());
+        assert_eq!(
+            (first, second, hit),
+            ("Ann".into(), "Bob".into(), "Ann".into())
+        );
+    }
+
+    #[test]
+    fn least_recently_used() {
+        let cache = MessageCache::new(2);
+        let lang = langid!("en");
+        cache.get_or_insert(0, &lang, "a", None, || "a".to_string());
+        cache.get_or_insert(0, &lang, "b", None, || "b".to_string());
+        cache.get_or_insert(0, &lang, "a", None, || unreachable!());
+        // Evicts `b` which is the least recently used
+        cache.get_or_insert(0, &lang, "c", None, || "c".to_string());
+        cache.get_or_insert(0, &lang, "a", None, || unreachable!());
+        let message =
+            cache.get_or_insert(0, &lang, "b", None, || "again".to_string());
+        assert_eq!(message, "again");
+    }
+
+    #[test]
+    fn generation() {
+        let cache = MessageCache::new(2);
+        let lang = langid!("en");
+        cache.get_or_insert(0, &lang, "a", None, || "a".to_string());
+        let message =
+            cache.get_or_insert(1, &lang, "a", None, || "new".to_string());
+        assert_eq!(message, "new");
+    }
+}
@@ -0,0 +1,59 @@
+//! Capture the output of a block rendered by the current renderer.
+use std::cell::RefCell;
+use std::io::{Result, Write};
+
+use bracket::{
+    helper::HelperResult, output::Output, parser::ast::Node, render::Render,
+};
+
+thread_local! {
+    /// Buffers for the blocks being captured, nested captures
+    /// write to the last buffer.
+    static BUFFERS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Output that writes to the last capture buffer.
+struct Capture;
+
+impl Output for Capture {
+    fn write_str(&mut self, s: &str) -> Result<usize> {
+        BUFFERS.with(|buffers| {
+            if let Some(buffer) = buffers.borrow_mut().last_mut() {
+                buffer.push_str(s);
+            }
+        });
+        Ok(s.len())
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.write_str(&String::from_utf8_lossy(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Render a block and return the output.
+///
+/// Unlike `Render::buffer()` the block is rendered by the current
+/// renderer so the partials of the render, including
+/// `@partial-block`, and the local helpers are available.
+pub(crate) fn block<'render>(
+    rc: &mut Render<'render>,
+    node: &'render Node<'render>,
+) -> HelperResult<String> {
+    BUFFERS.with(|buffers| buffers.borrow_mut().push(String::new()));
+    // The output has no size so leaking it does not allocate
+    let capture: &'render mut dyn Output = Box::leak(Box::new(Capture));
+    let previous = std::mem::replace(rc.out(), Box::new(capture));
+    let result = rc.template(node);
+    *rc.out() = previous;
+    let content = BUFFERS
+        .with(|buffers| buffers.borrow_mut().pop())
+        .unwrap_or_default();
+    result.map(|_| content)
+}
@@ -0,0 +1,507 @@
+//! Catalog of fluent resources that exposes the underlying bundles.
+use std::collections::HashMap;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{message, vars};
+use fluent_syntax::ast::{Comment, Entry, Pattern, ResourceEntry};
+use fluent_templates::fluent_bundle::{
+    concurrent::FluentBundle, resolve::ResolverError, FluentError,
+    FluentResource, FluentValue,
+};
+
+use fluent_templates::{
+    fs::read_from_file, loader::build_fallbacks, LanguageIdentifier, Loader,
+    LoaderError,
+};
+
+/// Bundle type used by a catalog.
+pub type CatalogBundle = FluentBundle<Arc<FluentResource>>;
+
+/// Builder for a catalog.
+pub struct CatalogBuilder<'a, 'b> {
+    location: &'a Path,
+    fallback: LanguageIdentifier,
+    shared: Option<&'b [PathBuf]>,
+    customize: Option<fn(&mut CatalogBundle)>,
+}
+
+impl<'a, 'b> CatalogBuilder<'a, 'b> {
+    /// Add fluent resources that are shared across all languages.
+    pub fn shared_resources(mut self, shared: Option<&'b [PathBuf]>) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Customize each bundle.
+    pub fn customize(mut self, customize: fn(&mut CatalogBundle)) -> Self {
+        self.customize = Some(customize);
+        self
+    }
+
+    /// Build the catalog.
+    pub fn build(self) -> Result<Catalog, Box<dyn std::error::Error>> {
+        let mut shared = Vec::new();
+        for path in self.shared.unwrap_or(&[]) {
+            shared.push(Arc::new(read_from_file(path)?));
+        }
+
+        let mut bundles = HashMap::new();
+        let mut resources = HashMap::new();
+        for entry in read_dir(self.location)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                let lang = name.parse::<LanguageIdentifier>()?;
+                let mut bundle =
+                    CatalogBundle::new(std::slice::from_ref(&lang));
+                let mut list: Vec<Arc<FluentResource>> =
+                    shared.iter().map(Arc::clone).collect();
+                for res in read_resources(&entry.path())? {
+                    list.push(Arc::new(res));
+                }
+                for res in list.iter() {
+                    bundle.add_resource(Arc::clone(res)).map_err(|errors| {
+                        LoaderError::FluentBundle { errors }
+                    })?;
+                }
+                if let Some(customize) = self.customize {
+                    (customize)(&mut bundle);
+                }
+                resources.insert(lang.clone(), list);
+                bundles.insert(lang, bundle);
+            }
+        }
+
+        let fallbacks =
+            build_fallbacks(&bundles.keys().cloned().collect::<Vec<_>>());
+
+        Ok(Catalog {
+            bundles,
+            resources,
+            fallbacks,
+            fallback: self.fallback,
+        })
+    }
+}
+
+/// Read the fluent resources in a directory recursively.
+///
+/// Files are sorted by path so the order of resources is stable.
+fn read_resources(dir: &Path) -> Result<Vec<FluentResource>, LoaderError> {
+    let mut paths = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = read_dir(&dir).map_err(|source| LoaderError::Fs {
+            path: dir.clone(),
+            source,
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|e| e == "ftl") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths.into_iter().map(read_from_file).collect()
+}
+
+/// Catalog of fluent bundles loaded at runtime.
+///
+/// Uses the same directory layout as the `ArcLoader` but exposes the
+/// bundles so that helpers can access message attributes.
+///
+/// The catalog also implements `Loader` so it can be used as the
+/// loader for a `FluentHelper`.
+pub struct Catalog {
+    bundles: HashMap<LanguageIdentifier, CatalogBundle>,
+    resources: HashMap<LanguageIdentifier, Vec<Arc<FluentResource>>>,
+    fallback: LanguageIdentifier,
+    fallbacks: HashMap<LanguageIdentifier, Vec<LanguageIdentifier>>,
+}
+
+impl Catalog {
+    /// Create a catalog builder.
+    pub fn builder<P: AsRef<Path> + ?Sized>(
+        location: &P,
+        fallback: LanguageIdentifier,
+    ) -> CatalogBuilder<'_, '_> {
+        CatalogBuilder {
+            location: location.as_ref(),
+            fallback,
+            shared: None,
+            customize: None,
+        }
+    }
+
+    /// Get the fallback language.
+    pub fn fallback(&self) -> &LanguageIdentifier {
+        &self.fallback
+    }
+
+    /// Get the bundle for a language.
+    pub fn bundle(&self, lang: &LanguageIdentifier) -> Option<&CatalogBundle> {
+        self.bundles.get(lang)
+    }
+
+    /// Get the resources for a language.
+    pub fn resources(
+        &self,
+        lang: &LanguageIdentifier,
+    ) -> Option<&[Arc<FluentResource>]> {
+        self.resources.get(lang).map(|list| list.as_slice())
+    }
+
+    /// Get the message ids for a language.
+    ///
+    /// When the language has not been loaded the message ids
+    /// for the fallback language are returned.
+    pub fn message_ids(&self, lang: &LanguageIdentifier) -> Vec<&str> {
+        self.resources
+            .get(lang)
+            .or_else(|| self.resources.get(&self.fallback))
+            .into_iter()
+            .flatten()
+            .flat_map(|res| res.ast().body.iter())
+            .filter_map(|entry| match entry {
+                ResourceEntry::Entry(Entry::Message(msg)) => Some(msg.id.name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the term ids for a language without the leading `-`.
+    ///
+    /// When the language has not been loaded the term ids
+    /// for the fallback language are returned.
+    pub fn term_ids(&self, lang: &LanguageIdentifier) -> Vec<&str> {
+        self.resources
+            .get(lang)
+            .or_else(|| self.resources.get(&self.fallback))
+            .into_iter()
+            .flatten()
+            .flat_map(|res| res.ast().body.iter())
+            .filter_map(|entry| match entry {
+                ResourceEntry::Entry(Entry::Term(term)) => Some(term.id.name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the value of a message.
+    ///
+    /// Bundles are searched in the same way as `find()`.
+    pub fn message_value(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> Option<(&CatalogBundle, &Pattern<'_>)> {
+        self.find(lang, msg_id).and_then(|bundle| {
+            bundle
+                .get_message(msg_id)
+                .and_then(|msg| msg.value)
+                .map(|pattern| (bundle, pattern))
+        })
+    }
+
+    /// Determine if a message with a value exists without
+    /// formatting the message.
+    ///
+    /// Bundles are searched in the same way as `find()`; a message
+    /// that only has attributes does not exist.
+    pub fn has_message(&self, lang: &LanguageIdentifier, msg_id: &str) -> bool {
+        self.message_value(lang, msg_id).is_some()
+    }
+
+    /// Get the value of a message attribute.
+    ///
+    /// Bundles are searched in the same way as `find()`.
+    pub fn message_attribute(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+        attr_name: &str,
+    ) -> Option<(&CatalogBundle, &Pattern<'_>)> {
+        self.find(lang, msg_id).and_then(|bundle| {
+            bundle
+                .get_message(msg_id)
+                .and_then(|msg| msg.attributes.get(attr_name).copied())
+                .map(|pattern| (bundle, pattern))
+        })
+    }
+
+    /// Get the languages to search for a language.
+    ///
+    /// Uses the same fallback chain as the loaders; the fallback
+    /// language is searched last when `default` is set.
+    fn chain<'a>(
+        &'a self,
+        lang: &LanguageIdentifier,
+        default: bool,
+    ) -> impl Iterator<Item = &'a LanguageIdentifier> {
+        self.fallbacks
+            .get(lang)
+            .into_iter()
+            .flatten()
+            .chain(std::iter::once(&self.fallback).filter(move |_| default))
+    }
+
+    /// Find the first bundle containing a message.
+    ///
+    /// Bundles are searched using the same fallback chain
+    /// as the loaders; the fallback language is searched last.
+    pub fn find(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> Option<&CatalogBundle> {
+        self.chain(lang, true)
+            .filter_map(|lang| self.bundles.get(lang))
+            .find(|bundle| bundle.has_message(msg_id))
+    }
+
+    /// Find the first bundle containing a message with a value
+    /// without searching the fallback language.
+    pub fn find_no_default_fallback(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> Option<&CatalogBundle> {
+        self.chain(lang, false)
+            .filter_map(|lang| self.bundles.get(lang))
+            .find(|bundle| {
+                bundle
+                    .get_message(msg_id)
+                    .is_some_and(|msg| msg.value.is_some())
+            })
+    }
+
+    /// Resolve a message without searching the fallback language.
+    ///
+    /// Returns `None` when the language does not have the message
+    /// so callers can try other languages in their own order.
+    pub fn lookup_no_default_fallback(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+        args: Option<&HashMap<String, FluentValue>>,
+    ) -> Option<String> {
+        let bundle = self.find_no_default_fallback(lang, msg_id)?;
+        let pattern = bundle.get_message(msg_id)?.value?;
+        Some(format_pattern(bundle, pattern, args, msg_id, lang))
+    }
+
+    /// Find the first bundle containing a term and the term value.
+    ///
+    /// The term id should not include the leading `-`.
+    pub fn find_term(
+        &self,
+        lang: &LanguageIdentifier,
+        term_id: &str,
+    ) -> Option<(&CatalogBundle, &Pattern<'_>)> {
+        self.find_term_in(lang, term_id, true)
+    }
+
+    /// Find the first bundle containing a term and the term value
+    /// without searching the fallback language.
+    pub fn find_term_no_default_fallback(
+        &self,
+        lang: &LanguageIdentifier,
+        term_id: &str,
+    ) -> Option<(&CatalogBundle, &Pattern<'_>)> {
+        self.find_term_in(lang, term_id, false)
+    }
+
+    /// Find a term, the fallback language is searched last
+    /// when `default` is set.
+    fn find_term_in(
+        &self,
+        lang: &LanguageIdentifier,
+        term_id: &str,
+        default: bool,
+    ) -> Option<(&CatalogBundle, &Pattern<'_>)> {
+        self.chain(lang, default).find_map(|lang| {
+            let bundle = self.bundles.get(lang)?;
+            self.resources
+                .get(lang)?
+                .iter()
+                .flat_map(|res| res.ast().body.iter())
+                .find_map(|entry| match entry {
+                    ResourceEntry::Entry(Entry::Term(term))
+                        if term.id.name == term_id =>
+                    {
+                        Some((bundle, &term.value))
+                    }
+                    _ => None,
+                })
+        })
+    }
+
+    /// Get the comment attached to a message.
+    ///
+    /// Resources are searched in the same way as `find()`; the lines
+    /// of the comment are joined with a newline. Group and resource
+    /// comments are not attached to a message.
+    pub fn message_comment(
+        &self,
+        lang: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> Option<String> {
+        let message = self.chain(lang, true).find_map(|lang| {
+            self.resources
+                .get(lang)?
+                .iter()
+                .flat_map(|res| res.ast().body.iter())
+                .find_map(|entry| match entry {
+                    ResourceEntry::Entry(Entry::Message(msg))
+                        if msg.id.name == msg_id =>
+                    {
+                        Some(msg)
+                    }
+                    _ => None,
+                })
+        })?;
+        match message.comment {
+            Some(Comment::Comment { ref content }) => Some(content.join("\n")),
+            _ => None,
+        }
+    }
+
+    /// Resolve the value of a term.
+    ///
+    /// The term id should not include the leading `-`.
+    pub fn lookup_term(
+        &self,
+        lang: &LanguageIdentifier,
+        term_id: &str,
+        args: Option<&HashMap<String, FluentValue>>,
+    ) -> Option<String> {
+        self.find_term(lang, term_id).map(|(bundle, pattern)| {
+            format_pattern(bundle, pattern, args, term_id, lang)
+        })
+    }
+
+    /// Resolve the value of a term without searching the
+    /// fallback language.
+    pub fn lookup_term_no_default_fallback(
+        &self,
+        lang: &LanguageIdentifier,
+        term_id: &str,
+        args: Option<&HashMap<String, FluentValue>>,
+    ) -> Option<String> {
+        self.find_term_no_default_fallback(lang, term_id).map(
+            |(bundle, pattern)| {
+                format_pattern(bundle, pattern, args, term_id, lang)
+            },
+        )
+    }
+}
+
+/// Message with a plural selector used to prepare a bundle.
+static PLURAL_SOURCE: &str =
+    "plural = { 1 ->\n    [one] one\n   *[other] other\n}\n";
+
+/// Prepare a bundle before it is used to format messages.
+///
+/// A bundle creates the plural rules for its language the first time
+/// a message selects a plural category so we format a pattern with a
+/// plural selector using the bundle.
+pub(crate) fn warm(bundle: &CatalogBundle) {
+    let resource = match FluentResource::try_new(PLURAL_SOURCE.to_string()) {
+        Ok(resource) => resource,
+        Err(_) => return,
+    };
+    let pattern = resource.ast().body.iter().find_map(|entry| match entry {
+        ResourceEntry::Entry(Entry::Message(msg)) => msg.value.as_ref(),
+        _ => None,
+    });
+    if let Some(pattern) = pattern {
+        try_format_pattern(bundle, pattern, None);
+    }
+}
+
+/// Format a pattern from a bundle.
+///
+/// Errors are logged rather than returned, the formatted pattern
+/// will contain placeholders for any values that could not be resolved.
+pub(crate) fn format_pattern(
+    bundle: &CatalogBundle,
+    pattern: &Pattern<'_>,
+    args: Option<&HashMap<String, FluentValue>>,
+    msg_id: &str,
+    lang: &LanguageIdentifier,
+) -> String {
+    let (value, errors) = try_format_pattern(bundle, pattern, args);
+    if !errors.is_empty() {
+        vars::warn_missing(pattern, lang, msg_id, args);
+    }
+    for error in errors {
+        log::warn!(
+            "Error formatting message '{}' for language '{}': {:?}",
+            msg_id,
+            lang,
+            error
+        );
+    }
+    value
+}
+
+/// Format a pattern from a bundle and return any errors.
+pub(crate) fn try_format_pattern(
+    bundle: &CatalogBundle,
+    pattern: &Pattern<'_>,
+    args: Option<&HashMap<String, FluentValue>>,
+) -> (String, Vec<FluentError>) {
+    let args = args.map(|args| {
+        args.iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect::<HashMap<_, _>>()
+    });
+    let mut errors = Vec::new();
+    let value = bundle.format_pattern(pattern, args.as_ref(), &mut errors);
+    (value.into_owned(), errors)
+}
+
+/// Describe an error from formatting a pattern.
+pub(crate) fn describe_error(error: &FluentError) -> String {
+    match error {
+        FluentError::ResolverError(ResolverError::Reference(reference)) => {
+            reference.to_string()
+        }
+        FluentError::ResolverError(ResolverError::MissingDefault) => {
+            "Missing default variant".to_string()
+        }
+        FluentError::ResolverError(ResolverError::Cyclic) => {
+            "Cyclic reference".to_string()
+        }
+        FluentError::ResolverError(ResolverError::TooManyPlaceables) => {
+            "Too many placeables".to_string()
+        }
+        _ => format!("{:?}", error),
+    }
+}
+
+impl Loader for Catalog {
+    fn lookup_complete(
+        &self,
+        lang: &LanguageIdentifier,
+        text_id: &str,
+        args: Option<&HashMap<String, FluentValue>>,
+    ) -> String {
+        if let Some((bundle, pattern)) = self.message_value(lang, text_id) {
+            format_pattern(bundle, pattern, args, text_id, lang)
+        } else {
+            message::unknown(text_id)
+        }
+    }
+
+    fn locales(&self) -> Box<dyn Iterator<Item = &LanguageIdentifier> + '_> {
+        Box::new(self.bundles.keys())
+    }
+}
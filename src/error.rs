@@ -0,0 +1,166 @@
+//! Errors returned by the helpers.
+use std::fmt;
+
+use bracket::error::HelperError;
+
+use fluent_templates::LanguageIdentifier;
+
+/// Error for a helper call that converts into a `HelperError`.
+#[derive(Debug)]
+pub(crate) enum FluentError {
+    /// The language variable does not exist in the template data.
+    MissingLang { helper: String, lang_path: String },
+    /// The language is not a string or language object, the source
+    /// describes where the language was found.
+    LangType { helper: String, source: String },
+    /// The language could not be parsed.
+    Parse(String),
+    /// The message does not exist for the language.
+    UnknownMessage {
+        helper: String,
+        msg_id: String,
+        lang: LanguageIdentifier,
+    },
+    /// The message exists but does not have the attribute.
+    UnknownAttribute {
+        helper: String,
+        msg_id: String,
+        attr: String,
+        lang: LanguageIdentifier,
+    },
+    /// Fluent returned errors when the message was formatted.
+    Resolution {
+        helper: String,
+        msg_id: String,
+        lang: LanguageIdentifier,
+        errors: Vec<String>,
+    },
+}
+
+impl FluentError {
+    /// Error for a message that does not exist.
+    pub fn unknown_message(
+        helper: &str,
+        msg_id: &str,
+        lang: &LanguageIdentifier,
+    ) -> Self {
+        Self::UnknownMessage {
+            helper: helper.to_string(),
+            msg_id: msg_id.to_string(),
+            lang: lang.clone(),
+        }
+    }
+}
+
+impl fmt::Display for FluentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingLang { helper, lang_path } => write!(
+                f,
+                "Helper '{}' requires a '{}' variable in the template data",
+                helper, lang_path
+            ),
+            Self::LangType { helper, source } => write!(
+                f,
+                "Type error in helper '{}' the {} must be a string or language object",
+                helper, source
+            ),
+            Self::Parse(message) => write!(f, "{}", message),
+            Self::UnknownMessage {
+                helper,
+                msg_id,
+                lang,
+            } => write!(
+                f,
+                "Helper '{}' could not find message '{}' for language '{}'",
+                helper, msg_id, lang
+            ),
+            Self::UnknownAttribute {
+                helper,
+                msg_id,
+                attr,
+                lang,
+            } => write!(
+                f,
+                "Helper '{}' could not find attribute '{}' of message '{}' for language '{}'",
+                helper, attr, msg_id, lang
+            ),
+            Self::Resolution {
+                helper,
+                msg_id,
+                lang,
+                errors,
+            } => write!(
+                f,
+                "Helper '{}' could not resolve message '{}' for language '{}': {}",
+                helper,
+                msg_id,
+                lang,
+                errors.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FluentError {}
+
+impl From<FluentError> for HelperError {
+    fn from(error: FluentError) -> Self {
+        HelperError::new(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::EN;
+
+    #[test]
+    fn messages() {
+        let helper = "fluent".to_string();
+        let error = FluentError::MissingLang {
+            helper: helper.clone(),
+            lang_path: "@root.lang".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Helper 'fluent' requires a '@root.lang' variable in the template data"
+        );
+        let error = FluentError::LangType {
+            helper: helper.clone(),
+            source: "'lang' parameter".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Type error in helper 'fluent' the 'lang' parameter must be a string or language object"
+        );
+        let error = FluentError::Parse("Invalid language".to_string());
+        assert_eq!(error.to_string(), "Invalid language");
+        let error = FluentError::unknown_message("fluent", "nope", &EN);
+        assert_eq!(
+            error.to_string(),
+            "Helper 'fluent' could not find message 'nope' for language 'en'"
+        );
+        let error = FluentError::UnknownAttribute {
+            helper: helper.clone(),
+            msg_id: "login".to_string(),
+            attr: "help".to_string(),
+            lang: EN.clone(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Helper 'fluent' could not find attribute 'help' of message 'login' for language 'en'"
+        );
+        let error = FluentError::Resolution {
+            helper,
+            msg_id: "greeting".to_string(),
+            lang: EN.clone(),
+            errors: vec!["Unknown variable: $name".to_string()],
+        };
+        let error: HelperError = error.into();
+        assert_eq!(
+            error.to_string(),
+            "Helper 'fluent' could not resolve message 'greeting' for language 'en': Unknown variable: $name"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Escape modes for resolved messages.
+use bracket::{escape::EscapeFn, Registry};
+
+/// Characters that are replaced by the default HTML escape function.
+static HTML_CHARS: [char; 5] = ['<', '>', '&', '"', '\''];
+
+thread_local! {
+    // Registry used to compare against the default escape function.
+    static DEFAULT: Registry<'static> = Registry::new();
+}
+
+/// Determine how messages are escaped when they are written.
+#[derive(Clone, Copy, Default)]
+pub enum EscapeMode {
+    /// Escape using the escape function of the registry
+    /// which escapes for HTML by default.
+    ///
+    /// When the registry uses the default escape function messages
+    /// without any characters to escape are written directly.
+    #[default]
+    Html,
+    /// Escape for a JSON string.
+    Json,
+    /// Do not escape messages.
+    None,
+    /// Escape using a custom function.
+    Custom(fn(&str) -> String),
+}
+
+/// Escape for a JSON string.
+///
+/// Quotes, backslashes and control characters are escaped,
+/// the surrounding quotes are not added.
+pub(crate) fn json(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => {
+                output.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// Reverse the default HTML escaping of the registry.
+///
+/// Only the entities written by the default escape function
+/// are decoded, `&amp;` is decoded last so that escaped entities
+/// are not decoded twice.
+pub(crate) fn unescape_html(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Determine if a message has no characters that the default
+/// HTML escape function would replace.
+pub(crate) fn is_html_plain(s: &str) -> bool {
+    !s.contains(&HTML_CHARS[..])
+}
+
+/// Determine if an escape function is the default escape function
+/// of a registry.
+///
+/// The function is compared by identity so an escape function
+/// assigned with `set_escape()` is not treated as the default
+/// even when it escapes in the same way.
+pub(crate) fn is_default(escape: &EscapeFn) -> bool {
+    DEFAULT.with(|registry| std::ptr::eq(&**registry.escape(), &**escape))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_plain() {
+        assert!(is_html_plain("Hello world!"));
+        assert!(is_html_plain(""));
+        for s in &["<b>", "a > b", "&amp;", "\"quoted\"", "it's"] {
+            assert!(!is_html_plain(s));
+        }
+    }
+
+    #[test]
+    fn default_escape() {
+        let mut registry = Registry::new();
+        assert!(is_default(registry.escape()));
+        registry.set_escape(Box::new(bracket::escape::noop));
+        assert!(!is_default(registry.escape()));
+        registry.set_escape(Box::new(|s| format!("[{}]", s)));
+        assert!(!is_default(registry.escape()));
+    }
+}
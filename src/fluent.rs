@@ -0,0 +1,2322 @@
+//! Helper for message lookup.
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use bracket::{
+    error::HelperError,
+    helper::{Helper, HelperResult, HelperValue, LocalHelper},
+    parser::ast::{Node, Slice},
+    registry::Registry,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::LanguageIdentifier;
+use fluent_templates::Loader;
+
+use crate::{
+    args::{
+        self, ArgsTransformFn, Conversion, ConvertFn, CAPTURE_BLOCK, DEFAULT,
+        FLATTEN_SEPARATOR, INLINE, LIST_SEPARATOR,
+    },
+    cache::MessageCache,
+    capture,
+    catalog::{self, Catalog},
+    error::FluentError,
+    escape::{self, EscapeMode},
+    inline,
+    lang::{self, LangCache, LangSettings, Pinned, LANG_PATH},
+    message::{self, is_missing, OnMissing},
+    number::{self, NumberFormatFn},
+    shared::SharedLoader,
+    suggest,
+    validate::{self, TemplateProblem, UnknownUsage, UnusedReport},
+};
+
+static FLUENT: &str = "fluent";
+static FLUENT_PARAM: &str = "fluentparam";
+static TYPE: &str = "type";
+static TRIM: &str = "trim";
+static RAW: &str = "raw";
+static JSON: &str = "json";
+static NUMBER: &str = "number";
+static STRING: &str = "string";
+static NAMESPACE_SEPARATOR: &str = ":";
+static ABSOLUTE_ID: char = '!';
+static MISS_ID: &str = "{id}";
+static MISS_LANG: &str = "{lang}";
+
+/// Maximum number of parameter maps kept for reuse by block calls.
+const PARAM_POOL_CAPACITY: usize = 8;
+
+/// Type for functions that resolve the text for missing messages.
+///
+/// The function receives the message id (without any namespace prefix)
+/// and the language identifier resolved for the call; the returned text
+/// is written in place of the message and escaped in the same way as
+/// a message.
+///
+/// The function is not called in strict mode or when the call
+/// has a `default` hash parameter.
+pub type MissingFn =
+    Box<dyn Fn(&str, &LanguageIdentifier) -> String + Send + Sync>;
+
+/// Type for functions that observe missing messages.
+///
+/// The function receives the message id (without any namespace prefix)
+/// and the language identifier resolved for the call; it is called for
+/// every missing message before the outcome for the miss is chosen,
+/// including in strict mode and when the `else` block of a block
+/// call is rendered.
+pub type MissObserverFn = Box<dyn Fn(&str, &LanguageIdentifier) + Send + Sync>;
+
+/// Value captured by a `{{#fluentparam}}` block.
+#[derive(Clone)]
+pub(crate) enum Captured {
+    /// Value passed to the message as it is.
+    Value(FluentValue<'static>),
+    /// JSON value converted in the same way as a hash parameter.
+    Json(Value),
+}
+
+// Fluent values are not `Sync` so we need a `Mutex` rather
+// than a `RwLock` to share them with the local helper.
+pub(crate) type Parameters = Arc<Mutex<HashMap<String, Captured>>>;
+
+/// Local helper for `{{#fluentparam}}` blocks.
+///
+/// The block content is passed as a string unless the `type`
+/// hash parameter is `number` in which case the trimmed content
+/// is parsed as a number; set the `trim` hash parameter to remove
+/// leading and trailing whitespace from a string.
+///
+/// Set the `json` hash parameter to parse the content as JSON, the
+/// value is converted in the same way as a hash parameter so arrays
+/// are joined and objects are flattened using the helper settings.
+///
+/// The content is captured after it is rendered so variables in the
+/// block are already escaped by the registry; set the `raw` hash
+/// parameter to decode the escaped content so the message is only
+/// escaped once when it is written.
+#[derive(Clone)]
+pub struct FluentParam {
+    parameters: Parameters,
+}
+
+impl FluentParam {
+    /// Create a block parameter helper that stores values in
+    /// the shared parameters.
+    pub(crate) fn new(parameters: Parameters) -> Self {
+        Self { parameters }
+    }
+}
+
+impl Helper for FluentParam {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+        ctx.assert_block(template)?;
+
+        let param_name = args::string_arg(ctx, 0)?;
+
+        let node = template.ok_or_else(|| {
+            HelperError::new(format!(
+                "Helper '{}' requires a block template",
+                ctx.name()
+            ))
+        })?;
+        let mut content = rc.buffer(node)?;
+
+        let trim = if let Some(value) = ctx.param(TRIM) {
+            value.as_bool().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' the 'trim' parameter must be a boolean",
+                    ctx.name()
+                ))
+            })?
+        } else {
+            false
+        };
+        if trim {
+            content = content.trim().to_string();
+        }
+
+        let raw = if let Some(value) = ctx.param(RAW) {
+            value.as_bool().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' the 'raw' parameter must be a boolean",
+                    ctx.name()
+                ))
+            })?
+        } else {
+            false
+        };
+        if raw {
+            content = escape::unescape_html(&content);
+        }
+
+        let json = if let Some(value) = ctx.param(JSON) {
+            value.as_bool().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' the 'json' parameter must be a boolean",
+                    ctx.name()
+                ))
+            })?
+        } else {
+            false
+        };
+
+        let value = match (json, ctx.param(TYPE)) {
+            (true, None) => {
+                let value = serde_json::from_str(&content).map_err(|e| {
+                    HelperError::new(format!(
+                        "Helper '{}' could not parse parameter '{}' as JSON: {}",
+                        ctx.name(),
+                        param_name,
+                        e
+                    ))
+                })?;
+                Captured::Json(value)
+            }
+            (true, Some(_)) => {
+                return Err(HelperError::new(format!(
+                    "Helper '{}' the 'json' parameter cannot be used with the 'type' parameter",
+                    ctx.name()
+                )))
+            }
+            (false, None) => Captured::Value(content.into()),
+            (false, Some(Value::String(kind))) if kind == STRING => {
+                Captured::Value(content.into())
+            }
+            (false, Some(Value::String(kind))) if kind == NUMBER => {
+                let number = content.trim().parse::<f64>().map_err(|_| {
+                    HelperError::new(format!(
+                        "Helper '{}' could not parse parameter '{}' as a number",
+                        ctx.name(),
+                        param_name
+                    ))
+                })?;
+                Captured::Value(number.into())
+            }
+            (false, Some(_)) => {
+                return Err(HelperError::new(format!(
+                    "Type error in helper '{}' the 'type' parameter must be 'string' or 'number'",
+                    ctx.name()
+                )))
+            }
+        };
+
+        let mut writer = self.parameters.lock().map_err(|_| {
+            HelperError::new(format!(
+                "Helper '{}' failed to lock the parameters",
+                ctx.name()
+            ))
+        })?;
+        writer.insert(param_name.to_string(), value);
+
+        Ok(None)
+    }
+}
+
+impl LocalHelper for FluentParam {}
+
+/// Lookup a language string in the underlying loader.
+///
+/// # Language
+///
+/// The language is found at the `lang_path` which is `@root.lang` by
+/// default; when the current scope has a `lang` field it takes
+/// precedence so a partial or block can change the language, and the
+/// `lang` hash parameter, which may be a path, overrides both:
+///
+/// ```text
+/// {{fluent "greeting" name="world" lang="fr"}}
+/// {{#with french}}{{fluent "greeting" name="world"}}{{/with}}
+/// ```
+///
+/// The language may be a language identifier, a POSIX locale such as
+/// `en_US.UTF-8` or an object with a `language` field and optional
+/// `script` and `region` fields. The `lang_aliases`, `accept_language`,
+/// `lang_index` and `default_lang` settings change how the language is
+/// resolved; without a language a loader with exactly one locale uses
+/// that locale. The `FluentPinHelper` block resolves the language once
+/// for the calls in the block.
+///
+/// # Arguments
+///
+/// Hash parameters are passed to the message except the parameters used
+/// by the helper. Numbers are passed as numbers for plural selection, as
+/// is a numeric string assigned to `count`; booleans are passed as the
+/// strings `true` and `false` so they can be used as variant keys,
+/// arrays are joined with the `list_separator` and objects are only
+/// passed when `flatten_depth` is set. The members of an object assigned
+/// to the `args` parameter are passed as arguments and a `null` value
+/// removes an inherited or default argument:
+///
+/// ```text
+/// {{fluent "receipt" args=order currency="EUR" site_name=null}}
+/// ```
+///
+/// A number argument is formatted with the options of the fluent
+/// `NUMBER()` function using parameters named after the argument with
+/// a `__` suffix; the formatted argument is passed as a string so it
+/// cannot be used as a selector. The `currency` style writes the symbol
+/// and fraction digits of the `currency` code without a `number_format`
+/// function and the `raw` option passes the number as an unformatted
+/// string:
+///
+/// ```text
+/// {{fluent "total" amount=42 amount__maximumFractionDigits=1}}
+/// {{fluent "total" amount=42 amount__style="currency" amount__currency="EUR"}}
+/// {{fluent "release" version=1234 version__raw=true}}
+/// ```
+///
+/// # Missing messages
+///
+/// When a message cannot be found the loader returns the text
+/// `Unknown localization` followed by the message id; the `default`
+/// hash parameter, the `missing` function and the `miss_format` are
+/// used instead in that order and in `strict` mode a missing message
+/// is an error. A message with an empty value is not missing, a message
+/// that only has attributes is, and because the result is compared with
+/// the text of the loader a message with exactly that text is missing
+/// too. The `on_missing` hash parameter
+/// chooses the outcome for a single call and is one of `error`,
+/// `empty`, `id`, `null` or `default`; the `else` block of a block
+/// call is rendered instead of a missing message:
+///
+/// ```text
+/// {{fluent "maybe-missing" default="N/A"}}
+/// {{#if (fluent "promo-banner" on_missing="null")}}...{{/if}}
+/// {{#fluent "maybe-missing"}}{{else}}Not translated{{/fluent}}
+/// ```
+///
+/// # Escaping
+///
+/// Messages are escaped with the escape function of the registry unless
+/// the call uses the triple-stash syntax or the `escape` hash parameter
+/// or setting is `false`; the `escape_mode` selects another escaping.
+/// Sub-expressions return the message without escaping so it can be
+/// passed to other helpers or partials:
+///
+/// ```text
+/// {{> card title=(fluent "card-title")}}
+/// ```
+///
+/// # Blocks
+///
+/// The `fluentparam` blocks of a block call are passed as arguments,
+/// whitespace in the block is written so use the `~` trim syntax and
+/// any other text is an error. Set the `inline` hash parameter to format
+/// the rendered block content as a fluent pattern, or `capture_block` to
+/// render the block with the partials of the current render, including
+/// `@partial-block`, and pass the output as the named argument:
+///
+/// ```text
+/// {{#fluent inline=true name=user.name}}Hello { $name }{{/fluent}}
+/// {{#fluent "card" capture_block="body"}}{{> @partial-block}}{{/fluent}}
+/// ```
+pub struct FluentHelper {
+    loader: SharedLoader,
+    /// Escape messages, default is `true`.
+    pub escape: bool,
+    /// How messages are escaped, default is `EscapeMode::Html`.
+    pub escape_mode: EscapeMode,
+    /// Path used to find the language identifier in the template data,
+    /// default is `@root.lang`.
+    pub lang_path: String,
+    lang_cache: LangCache,
+    /// Language identifiers for aliases of languages such as `english`
+    /// that are used instead of parsing the language.
+    pub lang_aliases: HashMap<String, LanguageIdentifier>,
+    /// Separator used to join array parameters, default is `", "`.
+    pub list_separator: String,
+    /// Function used to convert hash parameters before the
+    /// default conversion.
+    pub convert: Option<ConvertFn>,
+    /// Function used to format number arguments before the
+    /// default formatting.
+    pub number_format: Option<NumberFormatFn>,
+    /// Function used to transform the arguments for a language
+    /// before a message is resolved, default is `None`.
+    pub args_transform: Option<ArgsTransformFn>,
+    /// Levels of nested objects to flatten into arguments,
+    /// default is `0` which does not flatten objects.
+    pub flatten_depth: usize,
+    /// Separator used to join the keys of flattened objects,
+    /// default is `-` because fluent identifiers cannot contain `.`.
+    pub flatten_separator: String,
+    /// Function used to resolve the text for missing messages.
+    pub missing: Option<MissingFn>,
+    /// Template for the text of missing messages when there is
+    /// no `missing` function, `{id}` is replaced with the message
+    /// id and `{lang}` with the language, default is `None`.
+    pub miss_format: Option<String>,
+    /// Function called for every missing message, for example to
+    /// record metrics, default is `None`.
+    pub on_miss: Option<MissObserverFn>,
+    /// Return an error when a message is missing or, with a catalog,
+    /// when a message resolves with errors, default is `false`.
+    pub strict: bool,
+    /// Return an error when the language is not one of the
+    /// locales of the loader, default is `false`.
+    pub require_known_lang: bool,
+    /// Accept a number for the `lang` hash parameter as an index
+    /// into the sorted locales of the loader, default is `false`.
+    pub lang_index: bool,
+    /// Language used when the template data does not have a
+    /// language, default is `None` which uses the locale of a loader
+    /// with exactly one locale and otherwise returns an error.
+    pub default_lang: Option<LanguageIdentifier>,
+    /// Accept a quality ordered list of languages in the
+    /// `Accept-Language` format and choose the best match from the
+    /// locales of the loader, default is `false`.
+    pub accept_language: bool,
+    /// Catalog used to resolve terms and to suggest similar
+    /// message ids when a message is missing in strict mode; the
+    /// helpers that read attributes, comments or variables or check
+    /// that a message exists require a catalog.
+    pub catalog: Option<Arc<Catalog>>,
+    /// Name of the local helper for block parameters,
+    /// default is `fluentparam`.
+    pub param_helper: &'static str,
+    param_pool: Mutex<Vec<Parameters>>,
+    /// Pass the scalar fields of the root data as arguments,
+    /// default is `false`.
+    pub inherit_root_args: bool,
+    /// Arguments passed to every message.
+    ///
+    /// Values are converted in the same way as hash parameters; we
+    /// store JSON values because fluent values are not `Sync`.
+    pub default_args: HashMap<String, Value>,
+    /// Name of an argument assigned the resolved language
+    /// identifier unless it is already set, default is `None`.
+    pub lang_arg: Option<String>,
+    /// Keep the unicode isolation marks that fluent inserts around
+    /// placeables, default is `true`; works the same for every
+    /// loader type and for the helpers that format messages from
+    /// the catalog.
+    pub isolating: bool,
+    /// Trim leading and trailing whitespace from the final message,
+    /// default is `false`.
+    pub trim_output: bool,
+    /// Wrap messages in HTML comments with the message id for
+    /// translator review, default is `false`.
+    ///
+    /// Only applies to the `Html` escape mode and not to sub-expressions.
+    pub debug_annotate: bool,
+    /// Languages to try in order when a message is missing for
+    /// the requested language.
+    ///
+    /// Each language is looked up in the catalog on its own before the
+    /// loader searches its fallback language so a catalog is required.
+    pub fallbacks: Vec<LanguageIdentifier>,
+    /// Try the language without the region before the fallbacks
+    /// when a message is missing, default is `false`; requires a
+    /// catalog in the same way as `fallbacks`.
+    pub strip_region_fallback: bool,
+    /// Loaders for message ids prefixed with a namespace.
+    pub namespaces: HashMap<String, Box<dyn Loader + Send + Sync>>,
+    /// Separator between the namespace and the message id,
+    /// default is `:`.
+    pub namespace_separator: String,
+    /// Prefix prepended to message ids before the lookup, default
+    /// is `None`; ids that start with `!` and term ids are not prefixed.
+    pub id_prefix: Option<String>,
+    cache: Option<MessageCache>,
+}
+
+impl FluentHelper {
+    /// Create a builder for a fluent helper.
+    ///
+    /// ```
+    /// use bracket_fluent::FluentHelper;
+    /// use fluent_templates::ArcLoader;
+    /// use unic_langid::langid;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let loader = ArcLoader::builder("examples/locales", langid!("en")).build()?;
+    /// let helper = FluentHelper::builder(Box::new(loader))
+    ///     .lang_path("@root.site.lang")
+    ///     .default_lang(langid!("en"))
+    ///     .cache_capacity(1024)
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(
+        loader: Box<dyn Loader + Send + Sync>,
+    ) -> FluentHelperBuilder {
+        FluentHelperBuilder {
+            helper: Self::new(loader),
+        }
+    }
+
+    /// Create a new fluent helper.
+    ///
+    /// Messages are resolved using the underlying loader.
+    pub fn new(loader: Box<dyn Loader + Send + Sync>) -> Self {
+        Self::with_lang_path(loader, LANG_PATH)
+    }
+
+    /// Create a new fluent helper that resolves the language
+    /// identifier using the given path.
+    pub fn with_lang_path<S: AsRef<str>>(
+        loader: Box<dyn Loader + Send + Sync>,
+        lang_path: S,
+    ) -> Self {
+        Self {
+            loader: SharedLoader::new(loader),
+            escape: true,
+            escape_mode: Default::default(),
+            lang_path: lang_path.as_ref().to_string(),
+            lang_cache: Default::default(),
+            lang_aliases: HashMap::new(),
+            list_separator: LIST_SEPARATOR.to_string(),
+            convert: None,
+            number_format: None,
+            args_transform: None,
+            flatten_depth: 0,
+            flatten_separator: FLATTEN_SEPARATOR.to_string(),
+            missing: None,
+            miss_format: None,
+            on_miss: None,
+            strict: false,
+            require_known_lang: false,
+            lang_index: false,
+            default_lang: None,
+            accept_language: false,
+            catalog: None,
+            param_helper: FLUENT_PARAM,
+            param_pool: Default::default(),
+            inherit_root_args: false,
+            default_args: HashMap::new(),
+            lang_arg: None,
+            isolating: true,
+            trim_output: false,
+            debug_annotate: false,
+            fallbacks: Vec::new(),
+            strip_region_fallback: false,
+            namespaces: HashMap::new(),
+            namespace_separator: NAMESPACE_SEPARATOR.to_string(),
+            id_prefix: None,
+            cache: None,
+        }
+    }
+
+    /// Get a handle to the loader that can be used to replace
+    /// the loader after the helper has been registered.
+    pub fn shared_loader(&self) -> SharedLoader {
+        self.loader.clone()
+    }
+
+    /// Replace the loader.
+    pub fn reload(&self, loader: Box<dyn Loader + Send + Sync>) {
+        self.loader.reload(loader);
+    }
+
+    /// Prepare languages before the first render.
+    ///
+    /// The `ArcLoader`, the `StaticLoader` created by `static_loader!`
+    /// and the `Catalog` parse their resources when they are created so
+    /// there is nothing to load and for the loader this is a no-op. The
+    /// work that is deferred to the first call for a language is done
+    /// here instead: the language tags are added to the cache of parsed
+    /// languages and, with a `catalog`, the plural rules of the bundle
+    /// for each language are created.
+    pub fn warm(&self, langs: &[LanguageIdentifier]) {
+        for lang in langs {
+            self.lang_cache.insert(lang);
+            if let Some(bundle) = self
+                .catalog
+                .as_ref()
+                .and_then(|catalog| catalog.bundle(lang))
+            {
+                catalog::warm(bundle);
+            }
+        }
+    }
+
+    /// Find message ids used by the templates in a registry that
+    /// do not exist.
+    ///
+    /// Calls to the helper named `fluent` with a literal message id
+    /// are checked for every locale of the catalog without formatting
+    /// the messages. Without a catalog the messages cannot be checked
+    /// so no problems are reported; namespaced message ids are not in
+    /// the catalog so they are not checked.
+    pub fn validate_templates(
+        &self,
+        registry: &Registry<'_>,
+    ) -> Vec<TemplateProblem> {
+        let catalog = match self.catalog {
+            Some(ref catalog) => catalog,
+            None => return Vec::new(),
+        };
+        let mut locales: Vec<&LanguageIdentifier> = catalog.locales().collect();
+        locales.sort_by_key(|lang| lang.to_string());
+        let mut names: Vec<&String> = registry.templates().keys().collect();
+        names.sort();
+
+        let mut problems = Vec::new();
+        for name in names {
+            let template = &registry.templates()[name];
+            let mut found = Vec::new();
+            validate::usages(template.node(), FLUENT, INLINE, &mut found);
+            for usage in found {
+                let usage_id = match usage.msg_id {
+                    Some(msg_id) => msg_id,
+                    None => continue,
+                };
+                let msg_id = self.prefixed(usage_id);
+                if self.route(&msg_id).is_some() {
+                    continue;
+                }
+                let term_id = msg_id.strip_prefix('-');
+                for lang in locales.iter().copied() {
+                    // The loader searches the fallback language last
+                    let exists = match term_id {
+                        Some(term_id) => {
+                            catalog.find_term(lang, term_id).is_some()
+                        }
+                        None => catalog.has_message(lang, &msg_id),
+                    } || self
+                        .fallbacks_for(lang)
+                        .iter()
+                        .any(|lang| has(catalog, lang, &msg_id, term_id));
+                    if !exists {
+                        problems.push(TemplateProblem {
+                            template: name.to_string(),
+                            line: usage.line,
+                            msg_id: usage_id.to_string(),
+                            lang: lang.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        problems
+    }
+
+    /// Find message ids of the catalog that are not used by the
+    /// templates in a registry.
+    ///
+    /// Message ids for every locale of the `catalog` are compared with
+    /// the literal message ids of calls to the helper named `fluent`;
+    /// calls with a message id that is not a literal are reported as
+    /// unknown usage because they may use any message. References
+    /// between messages are not followed. Without a catalog the
+    /// message ids cannot be listed so none are reported as unused.
+    pub fn report_unused(&self, registry: &Registry<'_>) -> UnusedReport {
+        let mut names: Vec<&String> = registry.templates().keys().collect();
+        names.sort();
+
+        let mut report: UnusedReport = Default::default();
+        let mut used = HashSet::new();
+        for name in names {
+            let template = &registry.templates()[name];
+            let mut found = Vec::new();
+            validate::usages(template.node(), FLUENT, INLINE, &mut found);
+            for usage in found {
+                match usage.msg_id {
+                    Some(msg_id) => {
+                        let full_id = self.prefixed(msg_id);
+                        // Namespaced ids are not in the catalog
+                        if self.route(&full_id).is_none() {
+                            used.insert(full_id.into_owned());
+                        }
+                    }
+                    None => report.unknown_usage.push(UnknownUsage {
+                        template: name.to_string(),
+                        line: usage.line,
+                    }),
+                }
+            }
+        }
+
+        if let Some(ref catalog) = self.catalog {
+            let mut unused: Vec<String> = catalog
+                .locales()
+                .flat_map(|lang| catalog.message_ids(lang))
+                .filter(|msg_id| !used.contains(*msg_id))
+                .map(|msg_id| msg_id.to_string())
+                .collect();
+            unused.sort();
+            unused.dedup();
+            report.unused = unused;
+        }
+        report
+    }
+
+    /// Remove all messages from the message cache.
+    ///
+    /// Cached messages are also discarded when the loader is reloaded.
+    pub fn clear_cache(&self) {
+        if let Some(ref cache) = self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Get the languages to try when a message is missing.
+    fn fallbacks_for(
+        &self,
+        lang_id: &LanguageIdentifier,
+    ) -> Cow<'_, [LanguageIdentifier]> {
+        if self.strip_region_fallback && lang_id.region.is_some() {
+            let mut language = lang_id.clone();
+            language.region = None;
+            let mut fallbacks = Vec::with_capacity(self.fallbacks.len() + 1);
+            fallbacks.push(language);
+            fallbacks.extend(self.fallbacks.iter().cloned());
+            Cow::Owned(fallbacks)
+        } else {
+            Cow::Borrowed(&self.fallbacks)
+        }
+    }
+
+    /// Get the catalog used to look up each language of the
+    /// fallback chain on its own.
+    ///
+    /// Returns `None` without fallbacks and for namespaced messages
+    /// which are resolved by their loader; the loaders always search
+    /// their own fallback language so the chain requires a catalog.
+    fn chain_catalog(
+        &self,
+        ctx: &Context<'_>,
+        fallbacks: &[LanguageIdentifier],
+        namespaced: bool,
+    ) -> HelperResult<Option<&Catalog>> {
+        if fallbacks.is_empty() || namespaced {
+            return Ok(None);
+        }
+        self.catalog.as_deref().map(Some).ok_or_else(|| {
+            HelperError::new(format!(
+                "Helper '{}' requires a catalog to use fallback languages",
+                ctx.name()
+            ))
+        })
+    }
+
+    /// Get the catalog for helpers that require one.
+    pub(crate) fn require_catalog(
+        &self,
+        ctx: &Context<'_>,
+    ) -> HelperResult<&Catalog> {
+        self.catalog.as_deref().ok_or_else(|| {
+            HelperError::new(format!(
+                "Helper '{}' requires a fluent helper with a catalog",
+                ctx.name()
+            ))
+        })
+    }
+
+    /// Find the language that supplies a message.
+    ///
+    /// Each language of the fallback chain is checked in the catalog on
+    /// its own followed by the fallback language of the catalog without
+    /// formatting the message; returns `None` when no language has the
+    /// message.
+    ///
+    /// Without a catalog, and for namespaced messages which are not in
+    /// the catalog, the message is formatted by the loader instead.
+    pub(crate) fn message_lang(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+        lang_id: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> HelperResult<Option<LanguageIdentifier>> {
+        let catalog = match self.catalog.as_deref() {
+            Some(catalog) if self.route(msg_id).is_none() => catalog,
+            _ => return self.loader_message_lang(rc, ctx, lang_id, msg_id),
+        };
+        let term_id = msg_id.strip_prefix('-');
+        let fallbacks = self.fallbacks_for(lang_id);
+        let found = candidates(lang_id, &fallbacks)
+            .chain(std::iter::once(catalog.fallback()))
+            .find(|lang| has(catalog, lang, msg_id, term_id))
+            .cloned();
+        Ok(found)
+    }
+
+    /// Find the language that supplies a message using the loader.
+    ///
+    /// The loader can only determine if a message exists by formatting
+    /// it so the message is formatted with the arguments of the call,
+    /// the loaders panic when a variable of the message is missing. The
+    /// loader answers from its own fallback languages so the first
+    /// language of the fallback chain that resolves the message is
+    /// returned even when the message comes from a fallback language.
+    fn loader_message_lang(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+        lang_id: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> HelperResult<Option<LanguageIdentifier>> {
+        let default_loader = self.loader.get();
+        let (loader, msg_id) = self
+            .route(msg_id)
+            .unwrap_or((default_loader.as_ref(), msg_id));
+        let fallbacks = self.fallbacks_for(lang_id);
+        if let Some((catalog, term_id)) = self.term(ctx, msg_id)? {
+            let found = candidates(lang_id, &fallbacks)
+                .find(|lang| has(catalog, lang, msg_id, Some(term_id)))
+                .cloned();
+            return Ok(found);
+        }
+        let args = self.arguments(rc, ctx, lang_id, None)?;
+        let found = candidates(lang_id, &fallbacks)
+            .find(|lang| {
+                let message =
+                    loader.lookup_complete(lang, msg_id, args.as_ref());
+                !is_missing(&message, msg_id)
+            })
+            .cloned();
+        Ok(found)
+    }
+
+    /// Resolve the language for a call using the default loader.
+    pub(crate) fn lang(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+    ) -> HelperResult<LanguageIdentifier> {
+        let loader = self.loader.get();
+        self.resolve_lang(rc, ctx, loader.as_ref(), true, None)
+    }
+
+    /// Resolve the language to pin for a block using the default loader.
+    pub(crate) fn pin(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+    ) -> HelperResult<Pinned> {
+        let loader = self.loader.get();
+        lang::pin(rc, ctx, &self.lang_settings(loader.as_ref()))
+    }
+
+    /// Resolve the language for a call.
+    ///
+    /// When `check_known` is set and `require_known_lang` is enabled
+    /// the language must be one of the locales of the loader.
+    fn resolve_lang(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+        loader: &dyn Loader,
+        check_known: bool,
+        pinned: Option<&Pinned>,
+    ) -> HelperResult<LanguageIdentifier> {
+        let index = if self.lang_index {
+            lang::from_index(ctx, loader)?
+        } else {
+            None
+        };
+        let settings = self.lang_settings(loader);
+        let lang_id = match (index, pinned) {
+            (Some(lang_id), _) => lang_id,
+            (None, Some(pinned)) => {
+                lang::resolve_pinned(rc, ctx, &settings, pinned)?
+            }
+            (None, None) => lang::resolve_with(rc, ctx, &settings)?,
+        };
+
+        if check_known
+            && self.require_known_lang
+            && !loader.locales().any(|locale| *locale == lang_id)
+        {
+            return Err(HelperError::new(format!(
+                "Helper '{}' does not have a locale for language '{}'",
+                ctx.name(),
+                lang_id
+            )));
+        }
+
+        Ok(lang_id)
+    }
+
+    /// Get the settings for resolving a language with a loader.
+    fn lang_settings<'a>(&'a self, loader: &'a dyn Loader) -> LangSettings<'a> {
+        LangSettings {
+            lang_path: &self.lang_path,
+            cache: &self.lang_cache,
+            default: self.default_lang.as_ref(),
+            accept: if self.accept_language {
+                Some(loader)
+            } else {
+                None
+            },
+            aliases: if self.lang_aliases.is_empty() {
+                None
+            } else {
+                Some(&self.lang_aliases)
+            },
+            single_locale: loader,
+        }
+    }
+
+    /// Get the catalog and term id for a message id that is a term.
+    ///
+    /// Terms are not available from a loader so we
+    /// resolve them using the catalog.
+    fn term<'a>(
+        &'a self,
+        ctx: &Context<'_>,
+        msg_id: &'a str,
+    ) -> HelperResult<Option<(&'a Arc<Catalog>, &'a str)>> {
+        match msg_id.strip_prefix('-') {
+            Some(term_id) => {
+                let catalog = self.catalog.as_ref().ok_or_else(|| {
+                    HelperError::new(format!(
+                        "Helper '{}' requires a catalog to resolve term '{}'",
+                        ctx.name(),
+                        msg_id
+                    ))
+                })?;
+                Ok(Some((catalog, term_id)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get the options for converting JSON values to arguments.
+    fn conversion(&self) -> Conversion<'_> {
+        Conversion {
+            convert: self.convert.as_ref(),
+            list_separator: &self.list_separator,
+            flatten_depth: self.flatten_depth,
+            flatten_separator: &self.flatten_separator,
+        }
+    }
+
+    /// Insert the values captured by block parameters into
+    /// the arguments.
+    pub(crate) fn insert_captured<I>(
+        &self,
+        args: &mut HashMap<String, FluentValue<'static>>,
+        captured: I,
+    ) where
+        I: IntoIterator<Item = (String, Captured)>,
+    {
+        let conversion = self.conversion();
+        for (name, value) in captured {
+            match value {
+                Captured::Value(value) => {
+                    args.insert(name, value);
+                }
+                Captured::Json(value) => conversion.insert(args, &name, &value),
+            }
+        }
+    }
+
+    /// Build the message arguments from the hash parameters of a call.
+    ///
+    /// Extra arguments take precedence over the root data and default
+    /// arguments but not over hash parameters.
+    pub(crate) fn arguments(
+        &self,
+        rc: &Render<'_>,
+        ctx: &Context<'_>,
+        lang_id: &LanguageIdentifier,
+        extra: Option<&HashMap<String, FluentValue<'static>>>,
+    ) -> HelperResult<Option<HashMap<String, FluentValue<'static>>>> {
+        let conversion = self.conversion();
+        let mut args = args::from_parameters(ctx, &conversion);
+
+        if let Some(extra) = extra.filter(|extra| !extra.is_empty()) {
+            let args = args.get_or_insert_with(HashMap::new);
+            for (name, value) in extra {
+                args.entry(name.to_string())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+
+        if self.inherit_root_args {
+            args::inherit(
+                args.get_or_insert_with(HashMap::new),
+                ctx,
+                rc.data(),
+            );
+        }
+
+        if !self.default_args.is_empty() {
+            args::merge_defaults(
+                args.get_or_insert_with(HashMap::new),
+                ctx,
+                &self.default_args,
+                &conversion,
+            );
+        }
+
+        if let Some(ref mut args) = args {
+            number::format_arguments(
+                ctx,
+                args,
+                lang_id,
+                self.number_format.as_ref(),
+            )?;
+        }
+
+        if let Some(ref name) = self.lang_arg {
+            args.get_or_insert_with(HashMap::new)
+                .entry(name.to_string())
+                .or_insert_with(|| lang_id.to_string().into());
+        }
+
+        Ok(args)
+    }
+
+    /// Apply the arguments transform for a language.
+    pub(crate) fn transform_args(
+        &self,
+        lang_id: &LanguageIdentifier,
+        args: &mut Option<HashMap<String, FluentValue<'static>>>,
+    ) {
+        if let Some(ref transform) = self.args_transform {
+            (transform)(lang_id, args.get_or_insert_with(HashMap::new));
+        }
+    }
+
+    /// Format a message for a language.
+    ///
+    /// The message id may have a namespace prefix; fallbacks, the
+    /// cache and the handling for missing messages are applied in
+    /// the same way as for a call to the helper.
+    ///
+    /// Returns `None` when the message is missing and the outcome
+    /// for a missing message is `OnMissing::Null`.
+    pub(crate) fn format_message(
+        &self,
+        ctx: &Context<'_>,
+        lang_id: &LanguageIdentifier,
+        msg_id: &str,
+        args: Option<&HashMap<String, FluentValue<'static>>>,
+        on_missing: OnMissing,
+    ) -> HelperResult<Option<String>> {
+        // Namespaced ids are cached using the full id
+        let cache_id = msg_id;
+        let generation = self.loader.generation();
+        let default_loader = self.loader.get();
+        let (loader, msg_id) = self
+            .route(msg_id)
+            .unwrap_or((default_loader.as_ref(), msg_id));
+        let fallbacks = self.fallbacks_for(lang_id);
+        let term = self.term(ctx, msg_id)?;
+
+        let chain = self.chain_catalog(ctx, &fallbacks, cache_id != msg_id)?;
+        let term_id = term.map(|(_, term_id)| term_id);
+
+        let resolve = || {
+            // Each language of the chain is looked up on its own so the
+            // fallback language of the loader does not answer first
+            if let Some(catalog) = chain {
+                let found = candidates(lang_id, &fallbacks).find_map(|lang| {
+                    single(catalog, lang, msg_id, term_id, args)
+                        .map(|message| (lang, message))
+                });
+                if let Some((lang, message)) = found {
+                    if lang != lang_id {
+                        log::debug!(
+                            "Resolved message '{}' for language '{}' using fallback '{}'",
+                            msg_id,
+                            lang_id,
+                            lang
+                        );
+                    }
+                    return message;
+                }
+            }
+            match term {
+                Some((catalog, term_id)) => catalog
+                    .lookup_term(lang_id, term_id, args)
+                    .unwrap_or_else(|| message::unknown(msg_id)),
+                None => loader.lookup_complete(lang_id, msg_id, args),
+            }
+        };
+
+        // Resolution errors are checked before the lookup because
+        // a loader may panic when a message fails to format
+        if let (true, Some(catalog)) = (self.strict, self.catalog.as_ref()) {
+            // Check the language that supplied the message
+            let source = chain
+                .and_then(|catalog| {
+                    candidates(lang_id, &fallbacks)
+                        .find(|lang| has(catalog, lang, msg_id, term_id))
+                })
+                .unwrap_or(lang_id);
+            let pattern = match term {
+                Some((catalog, term_id)) => catalog.find_term(source, term_id),
+                // Namespaced messages are not in the catalog
+                None if cache_id == msg_id => {
+                    catalog.message_value(source, msg_id)
+                }
+                None => None,
+            };
+            if let Some((bundle, pattern)) = pattern {
+                let (_, errors) =
+                    catalog::try_format_pattern(bundle, pattern, args);
+                if !errors.is_empty() {
+                    return Err(FluentError::Resolution {
+                        helper: ctx.name().to_string(),
+                        msg_id: msg_id.to_string(),
+                        lang: lang_id.clone(),
+                        errors: errors
+                            .iter()
+                            .map(catalog::describe_error)
+                            .collect(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let mut message = match self.cache {
+            Some(ref cache) => cache
+                .get_or_insert(generation, lang_id, cache_id, args, resolve),
+            None => resolve(),
+        };
+
+        message = self.isolate(message);
+
+        if is_missing(&message, msg_id) {
+            if let Some(ref on_miss) = self.on_miss {
+                (on_miss)(msg_id, lang_id);
+            }
+            match on_missing {
+                OnMissing::Error => {
+                    return Err(self.missing_error(ctx, lang_id, msg_id))
+                }
+                OnMissing::Empty => message = String::new(),
+                OnMissing::Id => message = msg_id.to_string(),
+                OnMissing::Null => return Ok(None),
+                OnMissing::Default if self.strict => {
+                    return Err(self.missing_error(ctx, lang_id, msg_id))
+                }
+                OnMissing::Default => {
+                    if let Some(value) = ctx.param(DEFAULT) {
+                        message = value
+                            .as_str()
+                            .ok_or_else(|| {
+                                HelperError::new(format!(
+                                    "Type error in helper '{}' the 'default' parameter must be a string",
+                                    ctx.name()
+                                ))
+                            })?
+                            .to_string();
+                    } else if let Some(ref missing) = self.missing {
+                        message = (missing)(msg_id, lang_id);
+                    } else if let Some(ref miss_format) = self.miss_format {
+                        message = miss_format
+                            .replace(MISS_ID, msg_id)
+                            .replace(MISS_LANG, &lang_id.to_string());
+                    }
+                }
+            }
+        }
+
+        if self.trim_output {
+            message = message::trim(message);
+        }
+
+        Ok(Some(message))
+    }
+
+    /// Remove the isolation marks from a message
+    /// unless `isolating` is enabled.
+    pub(crate) fn isolate(&self, message: String) -> String {
+        if self.isolating {
+            message
+        } else {
+            message::strip_isolating(message)
+        }
+    }
+
+    /// Error for a missing message, with a catalog the error
+    /// suggests similar message ids.
+    fn missing_error(
+        &self,
+        ctx: &Context<'_>,
+        lang_id: &LanguageIdentifier,
+        msg_id: &str,
+    ) -> HelperError {
+        let mut error =
+            FluentError::unknown_message(ctx.name(), msg_id, lang_id)
+                .to_string();
+        if let Some(ref catalog) = self.catalog {
+            let candidates =
+                suggest::suggestions(msg_id, catalog.message_ids(lang_id));
+            if !candidates.is_empty() {
+                let candidates: Vec<String> =
+                    candidates.iter().map(|id| format!("'{}'", id)).collect();
+                error.push_str(&format!(
+                    ", did you mean {}?",
+                    candidates.join(", ")
+                ));
+            }
+        }
+        HelperError::new(error)
+    }
+
+    /// Write a message wrapped in comments with the message id
+    /// when `debug_annotate` is enabled.
+    pub(crate) fn write(
+        &self,
+        rc: &mut Render<'_>,
+        ctx: &Context<'_>,
+        msg_id: &str,
+        message: String,
+        escape_mode: EscapeMode,
+    ) -> HelperValue {
+        let annotate = self.debug_annotate
+            && matches!(escape_mode, EscapeMode::Html)
+            && !message::is_sub_expression(ctx);
+        if !annotate {
+            return message::write(rc, ctx, message, self.escape, escape_mode);
+        }
+
+        // Comments may not contain `--`
+        let msg_id = msg_id.replace("--", "-&#45;");
+        rc.write(&format!("<!--{}:{}-->", FLUENT, msg_id))?;
+        message::write(rc, ctx, message, self.escape, escape_mode)?;
+        rc.write(&format!("<!--/{}:{}-->", FLUENT, msg_id))?;
+        Ok(None)
+    }
+
+    /// Apply the id prefix to a message id.
+    ///
+    /// A leading `!` marks an absolute id which is returned without
+    /// the `!` and without the prefix; term ids are never prefixed.
+    pub(crate) fn prefixed<'a>(&self, msg_id: &'a str) -> Cow<'a, str> {
+        if let Some(id) = msg_id.strip_prefix(ABSOLUTE_ID) {
+            return Cow::Borrowed(id);
+        }
+        match self.id_prefix {
+            Some(ref prefix) if !msg_id.starts_with('-') => {
+                Cow::Owned(format!("{}{}", prefix, msg_id))
+            }
+            _ => Cow::Borrowed(msg_id),
+        }
+    }
+
+    /// Get the loader for a namespaced message id.
+    ///
+    /// When the message id is prefixed with a registered namespace
+    /// the loader for the namespace and the message id without
+    /// the prefix are returned.
+    fn route<'a>(
+        &'a self,
+        msg_id: &'a str,
+    ) -> Option<(&'a (dyn Loader + Send + Sync), &'a str)> {
+        if !self.namespaces.is_empty() {
+            if let Some(pos) = msg_id.find(&self.namespace_separator) {
+                let (name, id) = msg_id.split_at(pos);
+                if let Some(loader) = self.namespaces.get(name) {
+                    return Some((
+                        loader.as_ref(),
+                        &id[self.namespace_separator.len()..],
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Get the languages to try for a message in order, starting
+/// with the requested language.
+fn candidates<'a>(
+    lang_id: &'a LanguageIdentifier,
+    fallbacks: &'a [LanguageIdentifier],
+) -> impl Iterator<Item = &'a LanguageIdentifier> {
+    std::iter::once(lang_id)
+        .chain(fallbacks.iter().filter(move |lang| *lang != lang_id))
+}
+
+/// Resolve a message or term for a single language of a catalog.
+fn single(
+    catalog: &Catalog,
+    lang_id: &LanguageIdentifier,
+    msg_id: &str,
+    term_id: Option<&str>,
+    args: Option<&HashMap<String, FluentValue<'static>>>,
+) -> Option<String> {
+    match term_id {
+        Some(term_id) => {
+            catalog.lookup_term_no_default_fallback(lang_id, term_id, args)
+        }
+        None => catalog.lookup_no_default_fallback(lang_id, msg_id, args),
+    }
+}
+
+/// Determine if a single language of a catalog has a message or term.
+fn has(
+    catalog: &Catalog,
+    lang_id: &LanguageIdentifier,
+    msg_id: &str,
+    term_id: Option<&str>,
+) -> bool {
+    match term_id {
+        Some(term_id) => catalog
+            .find_term_no_default_fallback(lang_id, term_id)
+            .is_some(),
+        None => catalog.find_no_default_fallback(lang_id, msg_id).is_some(),
+    }
+}
+
+/// Builder for a fluent helper.
+pub struct FluentHelperBuilder {
+    helper: FluentHelper,
+}
+
+impl FluentHelperBuilder {
+    /// Set whether messages are escaped.
+    pub fn escape(mut self, escape: bool) -> Self {
+        self.helper.escape = escape;
+        self
+    }
+
+    /// Set how messages are escaped.
+    pub fn escape_mode(mut self, escape_mode: EscapeMode) -> Self {
+        self.helper.escape_mode = escape_mode;
+        self
+    }
+
+    /// Set the path used to find the language identifier.
+    pub fn lang_path<S: AsRef<str>>(mut self, lang_path: S) -> Self {
+        self.helper.lang_path = lang_path.as_ref().to_string();
+        self
+    }
+
+    /// Set the separator used to join array parameters.
+    pub fn list_separator<S: AsRef<str>>(mut self, list_separator: S) -> Self {
+        self.helper.list_separator = list_separator.as_ref().to_string();
+        self
+    }
+
+    /// Set the function used to convert hash parameters.
+    pub fn convert(mut self, convert: ConvertFn) -> Self {
+        self.helper.convert = Some(convert);
+        self
+    }
+
+    /// Set the function used to format number arguments.
+    pub fn number_format(mut self, number_format: NumberFormatFn) -> Self {
+        self.helper.number_format = Some(number_format);
+        self
+    }
+
+    /// Set the function used to transform the arguments for a language.
+    pub fn args_transform(mut self, args_transform: ArgsTransformFn) -> Self {
+        self.helper.args_transform = Some(args_transform);
+        self
+    }
+
+    /// Set the levels of nested objects to flatten into arguments.
+    ///
+    /// Each member of a flattened object is passed as an argument
+    /// named using the keys joined with the flatten separator so
+    /// `user=user` with a `name` field becomes `$user-name`.
+    pub fn flatten_depth(mut self, flatten_depth: usize) -> Self {
+        self.helper.flatten_depth = flatten_depth;
+        self
+    }
+
+    /// Set the separator used to join the keys of flattened objects.
+    ///
+    /// The default is `-` rather than `.` because a fluent identifier
+    /// may only contain letters, digits, `-` and `_` so a message could
+    /// not reference an argument such as `$user.name`; a separator
+    /// must only use those characters for the argument to be usable.
+    pub fn flatten_separator<S: AsRef<str>>(mut self, separator: S) -> Self {
+        self.helper.flatten_separator = separator.as_ref().to_string();
+        self
+    }
+
+    /// Set the function used to resolve the text for missing messages.
+    pub fn missing(mut self, missing: MissingFn) -> Self {
+        self.helper.missing = Some(missing);
+        self
+    }
+
+    /// Set the template for the text of missing messages.
+    pub fn miss_format<S: AsRef<str>>(mut self, miss_format: S) -> Self {
+        self.helper.miss_format = Some(miss_format.as_ref().to_string());
+        self
+    }
+
+    /// Set the function called for every missing message.
+    pub fn on_miss(mut self, on_miss: MissObserverFn) -> Self {
+        self.helper.on_miss = Some(on_miss);
+        self
+    }
+
+    /// Set whether missing messages are an error.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.helper.strict = strict;
+        self
+    }
+
+    /// Set whether languages unknown to the loader are an error.
+    pub fn require_known_lang(mut self, require_known_lang: bool) -> Self {
+        self.helper.require_known_lang = require_known_lang;
+        self
+    }
+
+    /// Set whether a number for the `lang` hash parameter is an index
+    /// into the sorted locales of the loader.
+    pub fn lang_index(mut self, lang_index: bool) -> Self {
+        self.helper.lang_index = lang_index;
+        self
+    }
+
+    /// Set the arguments passed to every message.
+    pub fn default_args(
+        mut self,
+        default_args: HashMap<String, Value>,
+    ) -> Self {
+        self.helper.default_args = default_args;
+        self
+    }
+
+    /// Set the catalog used to resolve terms and suggest message ids.
+    pub fn catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.helper.catalog = Some(catalog);
+        self
+    }
+
+    /// Set the name of the argument assigned the language identifier.
+    pub fn lang_arg<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.helper.lang_arg = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Set the name of the local helper for block parameters.
+    ///
+    /// The name must be `'static` as local helpers are registered
+    /// for the lifetime of the render.
+    pub fn param_helper(mut self, param_helper: &'static str) -> Self {
+        self.helper.param_helper = param_helper;
+        self
+    }
+
+    /// Set whether the fields of the root data are passed as arguments.
+    pub fn inherit_root_args(mut self, inherit_root_args: bool) -> Self {
+        self.helper.inherit_root_args = inherit_root_args;
+        self
+    }
+
+    /// Set whether the unicode isolation marks are kept in messages.
+    pub fn isolating(mut self, isolating: bool) -> Self {
+        self.helper.isolating = isolating;
+        self
+    }
+
+    /// Set the language used when the template data does not
+    /// have a language.
+    pub fn default_lang(mut self, lang: LanguageIdentifier) -> Self {
+        self.helper.default_lang = Some(lang);
+        self
+    }
+
+    /// Set whether a list of languages in the `Accept-Language`
+    /// format is negotiated against the locales of the loader.
+    pub fn accept_language(mut self, accept_language: bool) -> Self {
+        self.helper.accept_language = accept_language;
+        self
+    }
+
+    /// Add an alias for a language identifier.
+    pub fn lang_alias<S: AsRef<str>>(
+        mut self,
+        alias: S,
+        lang_id: LanguageIdentifier,
+    ) -> Self {
+        self.helper
+            .lang_aliases
+            .insert(alias.as_ref().to_string(), lang_id);
+        self
+    }
+
+    /// Set whether whitespace is trimmed from the final message.
+    pub fn trim_output(mut self, trim_output: bool) -> Self {
+        self.helper.trim_output = trim_output;
+        self
+    }
+
+    /// Set whether messages are wrapped in comments with the message id.
+    pub fn debug_annotate(mut self, debug_annotate: bool) -> Self {
+        self.helper.debug_annotate = debug_annotate;
+        self
+    }
+
+    /// Set the languages to try when a message is missing.
+    pub fn fallbacks(mut self, fallbacks: Vec<LanguageIdentifier>) -> Self {
+        self.helper.fallbacks = fallbacks;
+        self
+    }
+
+    /// Set whether to try the language without the region
+    /// before the fallbacks.
+    pub fn strip_region_fallback(mut self, strip: bool) -> Self {
+        self.helper.strip_region_fallback = strip;
+        self
+    }
+
+    /// Add a loader for message ids prefixed with a namespace.
+    pub fn namespace<S: AsRef<str>>(
+        mut self,
+        name: S,
+        loader: Box<dyn Loader + Send + Sync>,
+    ) -> Self {
+        self.helper
+            .namespaces
+            .insert(name.as_ref().to_string(), loader);
+        self
+    }
+
+    /// Set the separator between the namespace and the message id.
+    pub fn namespace_separator<S: AsRef<str>>(mut self, separator: S) -> Self {
+        self.helper.namespace_separator = separator.as_ref().to_string();
+        self
+    }
+
+    /// Set a prefix prepended to message ids before the lookup.
+    pub fn id_prefix<S: AsRef<str>>(mut self, prefix: S) -> Self {
+        self.helper.id_prefix = Some(prefix.as_ref().to_string());
+        self
+    }
+
+    /// Cache up to `capacity` formatted messages.
+    ///
+    /// Messages are cached by language, message id and arguments
+    /// and the least recently used message is evicted when the
+    /// cache is full.
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.helper.cache = Some(MessageCache::new(capacity));
+        self
+    }
+
+    /// Build the fluent helper.
+    pub fn build(self) -> FluentHelper {
+        self.helper
+    }
+}
+
+impl Helper for FluentHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        self.resolve(rc, ctx, template, self.escape_mode)
+    }
+}
+
+impl FluentHelper {
+    /// Resolve a message and write it using an escape mode.
+    pub(crate) fn resolve<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+        escape_mode: EscapeMode,
+    ) -> HelperValue {
+        self.resolve_with(rc, ctx, template, escape_mode, None, None)
+    }
+
+    /// Render a block with the block parameter helper registered and
+    /// insert the captured values into the arguments.
+    fn render_params<'render, T, F>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'_>,
+        args: &mut Option<HashMap<String, FluentValue<'static>>>,
+        render: F,
+    ) -> HelperResult<T>
+    where
+        F: FnOnce(&mut Render<'render>) -> HelperResult<T>,
+    {
+        // Reuse the parameter maps from previous calls
+        let parameters = self
+            .param_pool
+            .lock()
+            .ok()
+            .and_then(|mut pool| pool.pop())
+            .unwrap_or_default();
+        let local_helper = FluentParam {
+            parameters: Arc::clone(&parameters),
+        };
+        rc.register_local_helper(self.param_helper, Box::new(local_helper));
+        // Always unregister the local helper before propagating
+        // an error from the block so it does not leak
+        let result = render(rc);
+        rc.unregister_local_helper(self.param_helper);
+        let value = result?;
+
+        let mut map = parameters.lock().map_err(|_| {
+            HelperError::new(format!(
+                "Helper '{}' failed to lock the parameters",
+                ctx.name()
+            ))
+        })?;
+        let args =
+            args.get_or_insert_with(|| HashMap::with_capacity(map.len()));
+        self.insert_captured(args, map.drain());
+        drop(map);
+
+        if let Ok(mut pool) = self.param_pool.lock() {
+            if pool.len() < PARAM_POOL_CAPACITY {
+                pool.push(parameters);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Resolve a message with extra arguments that have a lower
+    /// precedence than the hash parameters and block parameters,
+    /// optionally using a language pinned by a block.
+    pub(crate) fn resolve_with<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+        escape_mode: EscapeMode,
+        extra: Option<&HashMap<String, FluentValue<'static>>>,
+        pinned: Option<&Pinned>,
+    ) -> HelperValue {
+        let inline = if let Some(value) = ctx.param(INLINE) {
+            value.as_bool().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' the 'inline' parameter must be a boolean",
+                    ctx.name()
+                ))
+            })?
+        } else {
+            false
+        };
+
+        // The message id is optional for inline patterns
+        let msg_id = if inline {
+            ctx.arity(0..1)?;
+            ctx.assert_block(template)?;
+            if ctx.arguments().is_empty() {
+                INLINE
+            } else {
+                args::msg_id_arg(ctx, 0)?
+            }
+        } else {
+            ctx.arity(1..1)?;
+            args::msg_id_arg(ctx, 0)?
+        };
+        let full_id = self.prefixed(msg_id);
+
+        let capture_block = match ctx.param(CAPTURE_BLOCK) {
+            Some(Value::String(name)) => {
+                ctx.assert_block(template)?;
+                Some(name.to_string())
+            }
+            Some(_) => {
+                return Err(HelperError::new(format!(
+                    "Type error in helper '{}' the 'capture_block' parameter must be a string",
+                    ctx.name()
+                )))
+            }
+            None => None,
+        };
+
+        // Namespaced ids are cached using the full id
+        let cache_id = full_id.as_ref();
+        let default_loader = self.loader.get();
+        let (loader, msg_id) = self
+            .route(cache_id)
+            .unwrap_or((default_loader.as_ref(), cache_id));
+
+        let lang_id = self.resolve_lang(rc, ctx, loader, !inline, pinned)?;
+        let mut args = self.arguments(rc, ctx, &lang_id, extra)?;
+
+        // Inline patterns use the block content as the message
+        if let (true, Some(node)) = (inline, template) {
+            // Render with this renderer so the block parameter
+            // helper is available to the block
+            let content = self.render_params(rc, ctx, &mut args, |rc| {
+                capture::block(rc, node)
+            })?;
+            self.transform_args(&lang_id, &mut args);
+            let mut message = inline::format(
+                ctx.name(),
+                msg_id,
+                &content,
+                &lang_id,
+                args.as_ref(),
+                self.isolating,
+            )?;
+            if self.trim_output {
+                message = message::trim(message);
+            }
+            return self.write(rc, ctx, cache_id, message, escape_mode);
+        }
+
+        // Render the inverse block instead when the message is missing
+        let inverse = match template {
+            Some(node) => rc.inverse(node)?,
+            None => None,
+        };
+
+        if let Some(node) = template {
+            // Text in the block would be written before the message
+            // unless the block is captured
+            if let (None, Node::Block(ref block)) = (&capture_block, node) {
+                let text = block.nodes().iter().any(|child| match child {
+                    Node::Text(text) => !text.as_str().trim().is_empty(),
+                    _ => false,
+                });
+                if text {
+                    return Err(HelperError::new(format!(
+                        "Helper '{}' block for message '{}' may only contain '{}' blocks",
+                        ctx.name(),
+                        msg_id,
+                        self.param_helper
+                    )));
+                }
+            }
+
+            let capture = capture_block.is_some();
+            let content = self.render_params(rc, ctx, &mut args, |rc| {
+                if capture {
+                    capture::block(rc, node).map(Some)
+                } else {
+                    rc.template(node).map(|_| None)
+                }
+            })?;
+            if let (Some(name), Some(content)) = (capture_block, content) {
+                args.get_or_insert_with(HashMap::new)
+                    .insert(name, content.into());
+            }
+        }
+
+        self.transform_args(&lang_id, &mut args);
+        let on_missing = match inverse {
+            Some(_) => OnMissing::Null,
+            None => message::on_missing(ctx)?,
+        };
+        let message = self.format_message(
+            ctx,
+            &lang_id,
+            cache_id,
+            args.as_ref(),
+            on_missing,
+        )?;
+        match (message, inverse) {
+            (Some(message), _) => {
+                self.write(rc, ctx, cache_id, message, escape_mode)
+            }
+            (None, Some(inverse)) => {
+                rc.template(inverse)?;
+                Ok(None)
+            }
+            (None, None) if message::is_sub_expression(ctx) => {
+                Ok(Some(Value::Null))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket::Registry;
+    use fluent_templates::ArcLoader;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_util::{self, catalog, helper, loader};
+    use unic_langid::langid;
+
+    fn render(
+        helper: FluentHelper,
+        template: &str,
+        data: Value,
+    ) -> bracket::Result<String> {
+        test_util::render("fluent", helper, template, data)
+    }
+
+    #[test]
+    fn number_format() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .number_format(Box::new(|number, _| {
+                let digits = (number.value as u64).to_string();
+                let (head, tail) = digits.split_at(digits.len() - 3);
+                let mut groups: Vec<String> = head
+                    .as_bytes()
+                    .rchunks(2)
+                    .rev()
+                    .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+                    .collect();
+                groups.push(tail.to_string());
+                Some(groups.join(","))
+            }))
+            .build();
+        let result = render(
+            helper,
+            r#"{{fluent "greeting" name=12345678}}"#,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(result.unwrap(), "Hello 1,23,45,678!");
+    }
+
+    #[test]
+    fn registry_escape() {
+        let mut registry = Registry::new();
+        registry.set_escape(Box::new(|s| format!("[{}]", s)));
+        registry.helpers_mut().insert(FLUENT, Box::new(helper()));
+        let data = json!({"lang": "en"});
+        let result = registry.once("test", r#"{{fluent "welcome"}}"#, &data);
+        assert_eq!(result.unwrap(), "[Hello!]");
+    }
+
+    #[test]
+    fn registry_default_escape() {
+        let mut registry = Registry::new();
+        registry.helpers_mut().insert(FLUENT, Box::new(helper()));
+        let data = json!({"lang": "en"});
+        let result = registry.once(
+            "test",
+            r#"{{fluent "welcome"}} {{fluent "raw-html"}}"#,
+            &data,
+        );
+        assert_eq!(result.unwrap(), "Hello! &lt;b&gt;Bold&lt;/b&gt;");
+    }
+
+    #[test]
+    fn capture_block() {
+        let mut registry = Registry::new();
+        registry.helpers_mut().insert(FLUENT, Box::new(helper()));
+        let card = r#"{{#fluent "card" capture_block="body"}}{{> @partial-block}}{{/fluent}}"#;
+        registry.insert("card", card).unwrap();
+        let data = json!({"lang": "en", "name": "Ann"});
+        let result =
+            registry.once("test", "{{#> card}}<b>{{name}}</b>{{/card}}", &data);
+        assert_eq!(result.unwrap(), "Card: &lt;b&gt;Ann&lt;/b&gt;");
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let template = r#"{{#fluent "block"}}{{#fluentparam "var1"}}{{#fluent "greeting"}}{{#fluentparam "name"}}Ann{{/fluentparam}}{{/fluent}}{{/fluentparam}}{{#fluentparam "var2"}}{{fluent "welcome"}}{{/fluentparam}}{{/fluent}}"#;
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann! Hello!");
+    }
+
+    #[test]
+    fn builder() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .escape(false)
+            .lang_path("@root.locale")
+            .build();
+        assert!(!helper.escape);
+        assert_eq!(helper.lang_path, "@root.locale");
+        let result =
+            render(helper, r#"{{fluent "raw-html"}}"#, json!({"locale": "en"}));
+        assert_eq!(result.unwrap(), "<b>Bold</b>");
+    }
+
+    #[test]
+    fn number_parameter() {
+        let template = r#"{{#fluent "items"}}{{#fluentparam "count" type="number"}} 1 {{/fluentparam}}{{/fluent}} {{#fluent "items"}}{{#fluentparam "count" type="number"}}3{{/fluentparam}}{{/fluent}}"#;
+        let data = json!({"lang": "en"});
+        let result =
+            render(FluentHelper::new(Box::new(loader())), template, data);
+        assert_eq!(result.unwrap(), "One item 3 items");
+
+        let template = r#"{{#fluent "items"}}{{#fluentparam "count" type="number"}}x{{/fluentparam}}{{/fluent}}"#;
+        let data = json!({"lang": "en"});
+        let result =
+            render(FluentHelper::new(Box::new(loader())), template, data);
+        assert!(result.unwrap_err().to_string().contains("count"));
+    }
+
+    #[test]
+    fn isolating() {
+        let loader = || {
+            ArcLoader::builder(test_util::LOCALES, test_util::EN.clone())
+                .build()
+                .unwrap()
+        };
+        let template = r#"{{fluent "greeting" name="Ann"}}"#;
+        let data = json!({"lang": "en"});
+        let result =
+            render(FluentHelper::new(Box::new(loader())), template, data);
+        assert_eq!(result.unwrap(), "Hello \u{2068}Ann\u{2069}!");
+
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .isolating(false)
+            .build();
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann!");
+
+        fluent_templates::static_loader! {
+            static LOCALES = {
+                locales: "./tests/fixtures/locales",
+                fallback_language: "en",
+                core_locales: "./tests/fixtures/locales/core.ftl",
+            };
+        }
+        let result = render(
+            FluentHelper::new(Box::new(&*LOCALES)),
+            template,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(result.unwrap(), "Hello \u{2068}Ann\u{2069}!");
+        let helper = FluentHelper::builder(Box::new(&*LOCALES))
+            .isolating(false)
+            .build();
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann!");
+    }
+
+    #[test]
+    fn namespaces() {
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let single = ArcLoader::builder(path, langid!("ar")).build().unwrap();
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .namespace("single", Box::new(single))
+            .build();
+        let template = r#"{{fluent "single:welcome"}} {{fluent "welcome"}}"#;
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "مرحبا Hello!");
+    }
+
+    #[test]
+    fn fallback_chain() {
+        let template =
+            r#"{{fluent "welcome"}} {{fluent "farewell"}} {{fluent "help"}}"#;
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .catalog(catalog())
+            .fallbacks(vec![langid!("de")])
+            .build();
+        let result = render(helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Bonjour! Auf Wiedersehen! Help");
+    }
+
+    #[test]
+    fn fallback_chain_requires_catalog() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .fallbacks(vec![langid!("de")])
+            .build();
+        let result =
+            render(helper, r#"{{fluent "farewell"}}"#, json!({"lang": "fr"}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("requires a catalog"));
+    }
+
+    #[test]
+    fn trim_parameter() {
+        let template = "{{#fluent \"greeting\"}}{{#fluentparam \"name\" trim=true}}\n  Ann\n{{/fluentparam}}{{/fluent}}|{{#fluent \"greeting\"}}{{#fluentparam \"name\"}} Ann {{/fluentparam}}{{/fluent}}";
+        let data = json!({"lang": "en"});
+        let result =
+            render(FluentHelper::new(Box::new(loader())), template, data);
+        assert_eq!(result.unwrap(), "Hello Ann!|Hello  Ann !");
+    }
+
+    #[test]
+    fn terms() {
+        let template = r#"{{fluent "-brand-name"}}"#;
+        let result = render(helper(), template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Acme");
+    }
+
+    #[test]
+    fn param_helper() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .param_helper("arg")
+            .build();
+        let template =
+            r#"{{#fluent "greeting"}}{{#arg "name"}}Ann{{/arg}}{{/fluent}}"#;
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann!");
+    }
+
+    #[test]
+    fn inverse_block() {
+        let template = r#"{{#fluent "greeting"}}{{#fluentparam "name"}}Ann{{/fluentparam}}{{else}}none{{/fluent}} {{#fluent "nope"}}{{else}}none{{/fluent}}"#;
+        let helper = FluentHelper::new(Box::new(loader()));
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann! none");
+    }
+
+    #[test]
+    fn many_arguments() {
+        let args: String =
+            (0..12).map(|i| format!(" arg{}={}", i, i)).collect();
+        let template = format!(
+            r#"{{{{#fluent "receipt" count=2{}}}}}{{{{#fluentparam "name"}}}}Ann{{{{/fluentparam}}}}{{{{#fluentparam "currency"}}}}EUR{{{{/fluentparam}}}}{{{{/fluent}}}}"#,
+            args
+        );
+        let result = render(helper(), &template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Ann bought 2 EUR");
+    }
+
+    #[test]
+    fn param_pool() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let mut registry = Registry::new();
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        let data = json!({"lang": "en"});
+        for _ in 0..3 {
+            let template = r#"{{#fluent "greeting"}}{{#fluentparam "name"}}Ann{{/fluentparam}}{{/fluent}}"#;
+            let result = registry.once("test", template, &data);
+            assert_eq!(result.unwrap(), "Hello Ann!");
+        }
+        // Parameters from previous calls are not reused
+        let template = r#"{{#fluent "receipt" name="Bob" count=1}}{{#fluentparam "currency"}}EUR{{/fluentparam}}{{/fluent}}"#;
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "Bob bought 1 EUR");
+    }
+
+    #[test]
+    fn missing() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .missing(Box::new(|msg_id, lang_id| {
+                format!("[{}:{}]", msg_id, lang_id)
+            }))
+            .build();
+        let template = r#"{{fluent "nope"}} {{fluent "nope" default="N/A"}}"#;
+        let result = render(helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "[nope:fr] N/A");
+    }
+
+    /// Render the block and ignore any error.
+    struct Recover;
+
+    impl Helper for Recover {
+        fn call<'render, 'call>(
+            &self,
+            rc: &mut Render<'render>,
+            _ctx: &Context<'call>,
+            template: Option<&'render Node<'render>>,
+        ) -> HelperValue {
+            if let Some(node) = template {
+                let _ = rc.template(node);
+            }
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn block_error() {
+        let mut registry = Registry::new();
+        registry.set_strict(true);
+        registry.helpers_mut().insert("fluent", Box::new(helper()));
+        registry.helpers_mut().insert("recover", Box::new(Recover));
+        let data = json!({"lang": "en"});
+        let call = r#"{{#fluent "greeting"}}{{#fluentparam "name" type="date"}}Ann{{/fluentparam}}{{/fluent}}"#;
+        let result = registry.once("test", call, &data);
+        assert!(result.unwrap_err().to_string().contains("'type' parameter"));
+
+        // The block parameter helper is not registered after the error
+        let template = format!(
+            r#"{{{{#recover}}}}{}{{{{/recover}}}}{{{{#fluentparam "name"}}}}Bob{{{{/fluentparam}}}}"#,
+            call
+        );
+        let result = registry.once("test", &template, &data);
+        assert!(result.unwrap_err().to_string().contains("fluentparam"));
+    }
+
+    #[test]
+    fn strip_region_fallback() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .catalog(catalog())
+            .strip_region_fallback(true)
+            .build();
+        let result = render(
+            helper,
+            r#"{{fluent "farewell"}}"#,
+            json!({"lang": "de-AT"}),
+        );
+        assert_eq!(result.unwrap(), "Auf Wiedersehen!");
+    }
+
+    #[test]
+    fn empty_block() {
+        let template = "{{#fluent \"welcome\"}}\n{{/fluent}}";
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "\nHello!");
+
+        let template = r#"{{#fluent "welcome"}}Hi{{/fluent}}"#;
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("may only contain 'fluentparam' blocks"));
+    }
+
+    #[test]
+    fn lang_arg() {
+        let lang_arg = || {
+            FluentHelper::builder(Box::new(loader()))
+                .lang_arg("lang")
+                .build()
+        };
+        let template =
+            r#"{{fluent "locale-note"}} {{fluent "locale-note" lang="fr"}}"#;
+        let result = render(lang_arg(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Other French");
+
+        let template = r#"{{#fluent "locale-note" lang="fr"}}{{#fluentparam "lang"}}de{{/fluentparam}}{{/fluent}}"#;
+        let result = render(lang_arg(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Other");
+    }
+
+    #[test]
+    fn registry_no_escape() {
+        let mut registry = Registry::new();
+        registry.set_escape(Box::new(bracket::escape::noop));
+        registry.helpers_mut().insert("fluent", Box::new(helper()));
+        let data = json!({"lang": "en"});
+        let result = registry.once("test", r#"{{fluent "raw-html"}}"#, &data);
+        assert_eq!(result.unwrap(), "<b>Bold</b>");
+    }
+
+    #[test]
+    fn validate_templates() {
+        let mut registry = Registry::new();
+        registry
+            .insert(
+                "page",
+                r#"{{fluent "welcome"}}{{fluent "farewell"}}
+{{fluent "nope"}}"#,
+            )
+            .unwrap();
+        let helper = helper();
+        let mut problems: Vec<(usize, String, String)> = helper
+            .validate_templates(&registry)
+            .into_iter()
+            .map(|p| (p.line, p.msg_id, p.lang.to_string()))
+            .collect();
+        problems.sort();
+        let expected: Vec<(usize, String, String)> = ["de", "en", "fr"]
+            .iter()
+            .map(|lang| (2, "nope".to_string(), lang.to_string()))
+            .collect();
+        assert_eq!(problems, expected);
+    }
+
+    #[test]
+    fn every_locale() {
+        let mut registry = Registry::new();
+        registry.helpers_mut().insert("fluent", Box::new(helper()));
+        registry.helpers_mut().insert(
+            "fluent_locales",
+            Box::new(crate::FluentLocalesHelper::new(helper())),
+        );
+        let template = r#"{{#each (fluent_locales)}}{{fluent "greeting" name=@index lang=this}} {{fluent "welcome"}} {{/each}}"#;
+        let data = json!({"lang": "en"});
+        for _ in 0..2 {
+            let result = registry.once("test", template, &data);
+            assert_eq!(
+                result.unwrap(),
+                "Hallo 0! Hello! Hello 1! Hello! Bonjour 2! Hello! "
+            );
+        }
+    }
+
+    #[test]
+    fn trim_output() {
+        let template = r#"[{{fluent "padded"}}]"#;
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "[  Padded  ]");
+
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .trim_output(true)
+            .build();
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "[Padded]");
+    }
+
+    #[test]
+    fn debug_annotate() {
+        let annotate = || {
+            FluentHelper::builder(Box::new(loader()))
+                .debug_annotate(true)
+                .build()
+        };
+        let template = r#"{{fluent "welcome"}}"#;
+        let result = render(annotate(), template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap(),
+            "<!--fluent:welcome-->Hello!<!--/fluent:welcome-->"
+        );
+
+        let template = r#"{{#if (fluent "welcome")}}yes{{/if}}"#;
+        let result = render(annotate(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "yes");
+
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .debug_annotate(true)
+            .escape_mode(EscapeMode::Json)
+            .build();
+        let template = r#"{{fluent "welcome"}}"#;
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello!");
+    }
+
+    #[test]
+    fn miss_format() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .miss_format("⟪{id}:{lang}⟫")
+            .build();
+        let template = r#"{{fluent "nope"}} {{fluent "welcome"}}"#;
+        let result = render(helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "⟪nope:de⟫ Hallo!");
+    }
+
+    #[test]
+    fn raw_parameter() {
+        let data = json!({"lang": "en", "company": "Smith & Sons"});
+        let template = r#"{{#fluent "greeting"}}{{#fluentparam "name"}}{{company}}{{/fluentparam}}{{/fluent}}"#;
+        let result = render(helper(), template, data.clone());
+        assert_eq!(result.unwrap(), "Hello Smith &amp;amp; Sons!");
+
+        let template = r#"{{#fluent "greeting"}}{{#fluentparam "name" raw=true}}{{company}}{{/fluentparam}}{{/fluent}}"#;
+        let result = render(helper(), template, data);
+        assert_eq!(result.unwrap(), "Hello Smith &amp; Sons!");
+    }
+
+    #[test]
+    fn empty_message() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .miss_format("[{id}]")
+            .build();
+        let template = r#"({{fluent "empty"}}) ({{fluent "nope"}})"#;
+        let result = render(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "() ([nope])");
+    }
+
+    #[test]
+    fn plural_counts() {
+        let template = r#"{{fluent "items" count=0}}, {{fluent "items" count=-1}}, {{fluent "items" count=2}}"#;
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "0 items, One item, 2 items");
+        let result = render(helper(), template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Un article, Un article, 2 articles");
+    }
+
+    #[test]
+    fn id_prefix() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .id_prefix("log")
+            .build();
+        let template = r#"{{fluent "in"}} {{fluent "!welcome"}}"#;
+        let result = render(helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Connexion Bonjour!");
+    }
+
+    #[test]
+    fn json_parameter() {
+        let template = r#"{{#fluent "list-items"}}{{#fluentparam "items" json=true}}["a", "b", 3]{{/fluentparam}}{{/fluent}}"#;
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Items: a, b, 3");
+
+        let template = r#"{{#fluent "list-items"}}{{#fluentparam "items" json=true}}["a",{{/fluentparam}}{{/fluent}}"#;
+        let result = render(helper(), template, json!({"lang": "en"}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("could not parse parameter 'items' as JSON"));
+    }
+
+    #[test]
+    fn report_unused() {
+        let mut registry = Registry::new();
+        registry
+            .insert("page", "{{fluent \"welcome\"}}\n{{fluent id}}")
+            .unwrap();
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let catalog = Catalog::builder(path, langid!("ar")).build().unwrap();
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .catalog(Arc::new(catalog))
+            .build();
+        let report = helper.report_unused(&registry);
+        assert_eq!(report.unused, vec!["farewell".to_string()]);
+        let usage: Vec<(&str, usize)> = report
+            .unknown_usage
+            .iter()
+            .map(|usage| (usage.template.as_str(), usage.line))
+            .collect();
+        assert_eq!(usage, vec![("page", 2)]);
+    }
+
+    #[test]
+    fn strict_resolution_errors() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .catalog(catalog())
+            .strict(true)
+            .build();
+        let result =
+            render(helper, r#"{{fluent "broken"}}"#, json!({"lang": "fr"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent' could not resolve message 'broken' for language 'fr': Unknown term: -missing-term"
+        );
+    }
+
+    #[test]
+    fn strict_resolution() {
+        let catalog = test_util::catalog();
+        let helper = FluentHelper::builder(Box::new(Arc::clone(&catalog)))
+            .catalog(catalog)
+            .strict(true)
+            .build();
+        let result =
+            render(helper, r#"{{fluent "greeting"}}"#, json!({"lang": "en"}));
+        assert!(result.unwrap_err().to_string().contains(
+            "could not resolve message 'greeting' for language 'en'"
+        ));
+    }
+
+    #[test]
+    fn on_miss() {
+        let misses = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&misses);
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .on_miss(Box::new(move |msg_id, lang_id| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:{}", msg_id, lang_id));
+            }))
+            .build();
+        let template = r#"{{fluent "nope" default="N/A"}} {{fluent "welcome"}} {{#fluent "gone"}}{{else}}-{{/fluent}}"#;
+        let result = render(helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "N/A Hallo! -");
+        assert_eq!(*misses.lock().unwrap(), vec!["nope:de", "gone:de"]);
+    }
+
+    #[test]
+    fn warm() {
+        let helper = helper();
+        helper.warm(&[langid!("fr"), langid!("en"), langid!("xx")]);
+        let template = r#"{{fluent "items" count=1}} {{fluent "items" count=1 lang="en"}}"#;
+        let result = render(helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Un article One item");
+
+        let helper = FluentHelper::new(Box::new(loader()));
+        helper.warm(&[langid!("de")]);
+        let result =
+            render(helper, r#"{{fluent "welcome"}}"#, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "Hallo!");
+    }
+}
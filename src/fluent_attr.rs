@@ -0,0 +1,202 @@
+//! Helper for message attribute lookup.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use crate::{args, catalog, error::FluentError, fluent::FluentHelper, message};
+
+/// Lookup a message attribute in a catalog.
+///
+/// The first argument is the message id and the second argument is
+/// the name of the attribute; the attribute is formatted with the hash
+/// parameters as arguments and escaped in the same way as a message.
+///
+/// The `Loader` trait cannot resolve attributes so the fluent helper
+/// must have a catalog. The message is looked up for the language and
+/// its locale fallbacks and then the fallback language of the catalog;
+/// the attribute is only read from the first language that has the
+/// message and it is an error when either does not exist. Namespace
+/// loaders are not searched so a namespaced message id must be in the
+/// catalog as it is.
+///
+/// ```text
+/// <button title="{{fluent_attr "login" "title"}}">{{fluent "login"}}</button>
+/// ```
+pub struct FluentAttrHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentAttrHelper {
+    /// Create a new fluent attribute helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentAttrHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(2..2)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let attr_name = args::string_arg(ctx, 1)?;
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let (bundle, pattern) = catalog
+            .message_attribute(&lang_id, &msg_id, attr_name)
+            .ok_or_else(|| {
+                if catalog.find(&lang_id, &msg_id).is_none() {
+                    FluentError::unknown_message(ctx.name(), &msg_id, &lang_id)
+                } else {
+                    FluentError::UnknownAttribute {
+                        helper: ctx.name().to_string(),
+                        msg_id: msg_id.to_string(),
+                        attr: attr_name.to_string(),
+                        lang: lang_id.clone(),
+                    }
+                }
+            })?;
+
+        let mut args = self.helper.arguments(rc, ctx, &lang_id, None)?;
+        self.helper.transform_args(&lang_id, &mut args);
+        let value = catalog::format_pattern(
+            bundle,
+            pattern,
+            args.as_ref(),
+            &msg_id,
+            &lang_id,
+        );
+        let value = self.helper.isolate(value);
+
+        message::write(
+            rc,
+            ctx,
+            value,
+            self.helper.escape,
+            self.helper.escape_mode,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render, stripped, with_namespace};
+
+    #[test]
+    fn attribute() {
+        let helper = FluentAttrHelper::new(helper());
+        let result = render(
+            "fluent_attr",
+            helper,
+            r#"{{fluent_attr "login" "title"}}"#,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(result.unwrap(), "Cliquez pour vous connecter");
+    }
+
+    #[test]
+    fn shared_settings() {
+        let mut helper = helper();
+        helper.id_prefix = Some("log".to_string());
+        helper
+            .lang_aliases
+            .insert("french".to_string(), langid!("fr"));
+        let helper = FluentAttrHelper::new(helper);
+        let result = render(
+            "fluent_attr",
+            helper,
+            r#"{{fluent_attr "in" "title"}}"#,
+            json!({"lang": "french"}),
+        );
+        assert_eq!(result.unwrap(), "Cliquez pour vous connecter");
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let helper = FluentAttrHelper::new(helper());
+        let result = render(
+            "fluent_attr",
+            helper,
+            r#"{{fluent_attr "login" "help"}}"#,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attr' could not find attribute 'help' of message 'login' for language 'fr'"
+        );
+    }
+
+    #[test]
+    fn isolating() {
+        let helper = FluentAttrHelper::new(stripped());
+        let result = render(
+            "fluent_attr",
+            helper,
+            r#"{{fluent_attr "signup" "hint" email="ann@example.com"}}"#,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(result.unwrap(), "Sent to ann@example.com");
+    }
+
+    #[test]
+    fn fallback_lang() {
+        let helper = FluentAttrHelper::new(helper());
+        let template = r#"{{fluent_attr "login" "title"}}"#;
+        let result =
+            render("fluent_attr", helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "Click to log in");
+    }
+
+    #[test]
+    fn missing_message() {
+        let helper = FluentAttrHelper::new(helper());
+        let template = r#"{{fluent_attr "nope" "title"}}"#;
+        let result =
+            render("fluent_attr", helper, template, json!({"lang": "fr"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attr' could not find message 'nope' for language 'fr'"
+        );
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentAttrHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{fluent_attr "login" "title"}}"#;
+        let result =
+            render("fluent_attr", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attr' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentAttrHelper::new(with_namespace());
+        let template = r#"{{fluent_attr "single:login" "title"}}"#;
+        let result =
+            render("fluent_attr", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attr' could not find message 'single:login' for language 'en'"
+        );
+    }
+}
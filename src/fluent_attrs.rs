@@ -0,0 +1,177 @@
+//! Helper to resolve all the attributes of a message.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{args, catalog, error::FluentError, fluent::FluentHelper};
+
+static VALUE_KEY: &str = "_";
+
+/// Resolve all the attributes of a message in a catalog.
+///
+/// Returns an object keyed by attribute name; when the message
+/// has a value it is included using the `value_key`. The value and
+/// every attribute are formatted with the hash parameters as arguments.
+///
+/// The fluent helper must have a catalog. The attributes are read from
+/// the first language of the locale fallbacks, or the fallback language
+/// of the catalog, that has the message so they are never mixed from
+/// different languages; it is an error when no language has the message
+/// and a namespaced message id must be in the catalog as it is.
+///
+/// ```text
+/// {{#with (fluent_attrs "field")}}{{this.label}} {{this.help}}{{/with}}
+/// ```
+pub struct FluentAttrsHelper {
+    helper: Arc<FluentHelper>,
+    /// Key used for the message value, default is `_`; when `None`
+    /// the message value is not included.
+    pub value_key: Option<String>,
+}
+
+impl FluentAttrsHelper {
+    /// Create a new fluent attributes helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            value_key: Some(VALUE_KEY.to_string()),
+        }
+    }
+}
+
+impl Helper for FluentAttrsHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let msg_id = msg_id.as_ref();
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let (bundle, message) = catalog
+            .find(&lang_id, msg_id)
+            .and_then(|bundle| {
+                bundle.get_message(msg_id).map(|message| (bundle, message))
+            })
+            .ok_or_else(|| {
+                FluentError::unknown_message(ctx.name(), msg_id, &lang_id)
+            })?;
+
+        let mut args = self.helper.arguments(rc, ctx, &lang_id, None)?;
+        self.helper.transform_args(&lang_id, &mut args);
+        let mut attributes = Map::new();
+        if let (Some(key), Some(pattern)) = (&self.value_key, message.value) {
+            let value = catalog::format_pattern(
+                bundle,
+                pattern,
+                args.as_ref(),
+                msg_id,
+                &lang_id,
+            );
+            let value = self.helper.isolate(value);
+            attributes.insert(key.to_string(), Value::String(value));
+        }
+        for (name, pattern) in message.attributes {
+            let value = catalog::format_pattern(
+                bundle,
+                pattern,
+                args.as_ref(),
+                msg_id,
+                &lang_id,
+            );
+            let value = self.helper.isolate(value);
+            attributes.insert(name.to_string(), Value::String(value));
+        }
+
+        Ok(Some(Value::Object(attributes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render, stripped, with_namespace};
+
+    #[test]
+    fn attributes() {
+        let helper = FluentAttrsHelper::new(helper());
+        let result = render(
+            "fluent_attrs",
+            helper,
+            r#"{{#with (fluent_attrs "login")}}{{this._}}: {{this.title}}{{/with}}"#,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(result.unwrap(), "Connexion: Cliquez pour vous connecter");
+    }
+
+    #[test]
+    fn isolating() {
+        let helper = FluentAttrsHelper::new(stripped());
+        let template = r#"{{#with (fluent_attrs "signup" name="Ann" email="ann@example.com")}}{{_}}|{{hint}}{{/with}}"#;
+        let result =
+            render("fluent_attrs", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Welcome Ann|Sent to ann@example.com");
+    }
+
+    #[test]
+    fn value_key() {
+        let mut helper = FluentAttrsHelper::new(helper());
+        helper.value_key = None;
+        let template =
+            r#"{{#each (fluent_attrs "login")}}{{@key}}={{this}};{{/each}}"#;
+        let result =
+            render("fluent_attrs", helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "title=Click to log in;");
+    }
+
+    #[test]
+    fn missing_message() {
+        let helper = FluentAttrsHelper::new(helper());
+        let template = r#"{{fluent_attrs "nope"}}"#;
+        let result =
+            render("fluent_attrs", helper, template, json!({"lang": "fr"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attrs' could not find message 'nope' for language 'fr'"
+        );
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentAttrsHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{fluent_attrs "login"}}"#;
+        let result =
+            render("fluent_attrs", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attrs' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentAttrsHelper::new(with_namespace());
+        let template = r#"{{fluent_attrs "single:welcome"}}"#;
+        let result =
+            render("fluent_attrs", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_attrs' could not find message 'single:welcome' for language 'en'"
+        );
+    }
+}
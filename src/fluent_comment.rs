@@ -0,0 +1,110 @@
+//! Helper to get the comment for a message.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{args, fluent::FluentHelper};
+
+/// Get the comment attached to a message in a catalog.
+///
+/// Returns the text of the comment above the message without the
+/// leading `#`; returns an empty string when the message does not
+/// exist or does not have a comment.
+///
+/// The fluent helper must have a catalog because comments are only
+/// kept in the parsed resources. The comment is read from the first
+/// language of the locale fallbacks, or the fallback language of the
+/// catalog, that has the message so a translation without a comment
+/// does not use the comment of another language. A namespaced message
+/// id must be in the catalog as it is.
+///
+/// ```text
+/// <span title="{{fluent_comment "greeting"}}">{{fluent "greeting"}}</span>
+/// ```
+pub struct FluentCommentHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentCommentHelper {
+    /// Create a new fluent comment helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentCommentHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let comment = catalog
+            .message_comment(&lang_id, &msg_id)
+            .unwrap_or_default();
+        Ok(Some(Value::String(comment)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render, with_namespace};
+
+    #[test]
+    fn comment() {
+        let helper = FluentCommentHelper::new(helper());
+        let template = r#"[{{fluent_comment "welcome"}}] [{{fluent_comment "greeting"}}] [{{fluent_comment "nope"}}]"#;
+        let result =
+            render("fluent_comment", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "[Shown on the home page] [] []");
+    }
+
+    #[test]
+    fn fallback_lang() {
+        let helper = FluentCommentHelper::new(helper());
+        let template = r#"[{{fluent_comment "welcome"}}] [{{fluent_comment "welcome" lang="ja"}}]"#;
+        let result =
+            render("fluent_comment", helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "[] [Shown on the home page]");
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentCommentHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{fluent_comment "welcome"}}"#;
+        let result =
+            render("fluent_comment", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_comment' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentCommentHelper::new(with_namespace());
+        let template = r#"[{{fluent_comment "single:welcome"}}]"#;
+        let result =
+            render("fluent_comment", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "[]");
+    }
+}
@@ -0,0 +1,136 @@
+//! Helper to find the text direction for a language.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use unic_langid::CharacterDirection;
+
+use crate::fluent::FluentHelper;
+
+static RTL: &str = "rtl";
+static LTR: &str = "ltr";
+
+/// Get the text direction for the language.
+///
+/// Returns `rtl` for right to left languages and scripts
+/// and `ltr` otherwise so it can be assigned to a `dir` attribute.
+///
+/// Only the language is resolved so a catalog is not required and a
+/// language without any messages still has a direction unless
+/// `require_known_lang` is set. Language aliases and `default_lang`
+/// are applied first and a script such as `az-Arab` determines the
+/// direction rather than the language.
+///
+/// ```text
+/// <html lang="{{@root.lang}}" dir="{{fluent_dir}}">
+/// ```
+pub struct FluentDirHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentDirHelper {
+    /// Create a new fluent direction helper using the settings
+    /// of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentDirHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+
+        let lang_id = self.helper.lang(rc, ctx)?;
+        let dir = match lang_id.character_direction() {
+            CharacterDirection::RTL => RTL,
+            CharacterDirection::LTR => LTR,
+        };
+
+        Ok(Some(Value::String(dir.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_templates::ArcLoader;
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render};
+
+    #[test]
+    fn direction() {
+        let helper =
+            FluentDirHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{fluent_dir}} {{fluent_dir lang="ar"}}"#;
+        let result =
+            render("fluent_dir", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "ltr rtl");
+    }
+
+    #[test]
+    fn single_locale() {
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let loader = ArcLoader::builder(path, langid!("ar")).build().unwrap();
+        let helper = FluentDirHelper::new(FluentHelper::new(Box::new(loader)));
+        let result = render("fluent_dir", helper, "{{fluent_dir}}", json!({}));
+        assert_eq!(result.unwrap(), "rtl");
+    }
+
+    #[test]
+    fn aliases_and_scripts() {
+        let mut helper = helper();
+        helper
+            .lang_aliases
+            .insert("arabic".to_string(), langid!("ar"));
+        let helper = FluentDirHelper::new(helper);
+        let template = r#"{{fluent_dir}} {{fluent_dir lang="az-Arab"}} {{fluent_dir lang="az"}}"#;
+        let result =
+            render("fluent_dir", helper, template, json!({"lang": "arabic"}));
+        assert_eq!(result.unwrap(), "rtl rtl ltr");
+    }
+
+    #[test]
+    fn missing_lang() {
+        let helper =
+            FluentDirHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = render("fluent_dir", helper, "{{fluent_dir}}", json!({}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_dir' requires a '@root.lang' variable in the template data"
+        );
+    }
+
+    #[test]
+    fn require_known_lang() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .require_known_lang(true)
+            .build();
+        let helper = FluentDirHelper::new(helper);
+        let result = render(
+            "fluent_dir",
+            helper,
+            "{{fluent_dir}}",
+            json!({"lang": "ar"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_dir' does not have a locale for language 'ar'"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Helper to resolve the messages in a catalog as an object.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{args, catalog, fluent::FluentHelper};
+
+/// Resolve the messages in a catalog for the current language.
+///
+/// Returns an object mapping message ids to the message values
+/// formatted without arguments; an optional argument restricts
+/// the messages to ids that start with a prefix. Messages without
+/// a value are not included.
+///
+/// The fluent helper must have a catalog to list the messages. Only the
+/// ids in the resources of the language are included, or the ids of the
+/// fallback language of the catalog when the language is not loaded, so
+/// messages that a language has not translated are left out rather than
+/// dumped in another language. The prefix is matched against the ids as
+/// they are in the catalog; the id prefix of the fluent helper is not
+/// applied and namespace loaders are not included.
+///
+/// ```text
+/// <script>const messages = {{{json (fluent_dump "nav-")}}};</script>
+/// ```
+pub struct FluentDumpHelper {
+    helper: Arc<FluentHelper>,
+    /// Include terms using the term id with the leading `-`,
+    /// default is `false`.
+    pub terms: bool,
+}
+
+impl FluentDumpHelper {
+    /// Create a new fluent dump helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            terms: false,
+        }
+    }
+}
+
+impl Helper for FluentDumpHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..1)?;
+
+        let prefix = if ctx.arguments().is_empty() {
+            ""
+        } else {
+            args::string_arg(ctx, 0)?
+        };
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let mut messages = Map::new();
+        for msg_id in catalog.message_ids(&lang_id) {
+            if !msg_id.starts_with(prefix) {
+                continue;
+            }
+            if let Some((bundle, pattern)) =
+                catalog.message_value(&lang_id, msg_id)
+            {
+                let value = catalog::format_pattern(
+                    bundle, pattern, None, msg_id, &lang_id,
+                );
+                let value = self.helper.isolate(value);
+                messages.insert(msg_id.to_string(), Value::String(value));
+            }
+        }
+
+        if self.terms {
+            for term_id in catalog.term_ids(&lang_id) {
+                let id = format!("-{}", term_id);
+                if !id.starts_with(prefix) {
+                    continue;
+                }
+                if let Some(value) =
+                    catalog.lookup_term(&lang_id, term_id, None)
+                {
+                    let value = self.helper.isolate(value);
+                    messages.insert(id, Value::String(value));
+                }
+            }
+        }
+
+        Ok(Some(Value::Object(messages)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{
+        self, helper, loader, render, stripped, with_namespace,
+    };
+
+    fn dump_lang(helper: FluentDumpHelper, prefix: &str, lang: &str) -> String {
+        let template = format!(
+            r#"{{{{#each (fluent_dump "{}")}}}}{{{{@key}}}}={{{{this}}}};{{{{/each}}}}"#,
+            prefix
+        );
+        render("fluent_dump", helper, &template, json!({ "lang": lang }))
+            .unwrap()
+    }
+
+    fn dump(helper: FluentDumpHelper, prefix: &str) -> String {
+        dump_lang(helper, prefix, "en")
+    }
+
+    #[test]
+    fn messages() {
+        let helper = FluentDumpHelper::new(helper());
+        assert_eq!(dump(helper, "raw"), "raw-html=&lt;b&gt;Bold&lt;/b&gt;;");
+        let helper = FluentDumpHelper::new(test_util::helper());
+        assert_eq!(dump(helper, "we"), "welcome=Hello!;");
+    }
+
+    #[test]
+    fn terms() {
+        let helper = FluentDumpHelper::new(helper());
+        assert_eq!(dump(helper, "-"), "");
+        let mut helper = FluentDumpHelper::new(test_util::helper());
+        helper.terms = true;
+        assert_eq!(dump(helper, "-"), "-brand-name=Acme;");
+    }
+
+    #[test]
+    fn isolating() {
+        let helper = FluentDumpHelper::new(stripped());
+        let template = r#"{{#with (fluent_dump "card")}}{{card}}{{/with}}"#;
+        let result =
+            render("fluent_dump", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Card: {$body}");
+    }
+
+    #[test]
+    fn languages() {
+        let helper = FluentDumpHelper::new(helper());
+        assert_eq!(dump_lang(helper, "we", "de"), "welcome=Hallo!;");
+        let helper = FluentDumpHelper::new(test_util::helper());
+        assert_eq!(dump_lang(helper, "login", "de"), "");
+        let helper = FluentDumpHelper::new(test_util::helper());
+        assert_eq!(dump_lang(helper, "f", "ja"), "farewell=Goodbye!;");
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentDumpHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = render(
+            "fluent_dump",
+            helper,
+            "{{fluent_dump}}",
+            json!({"lang": "en"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_dump' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentDumpHelper::new(with_namespace());
+        assert_eq!(dump(helper, "single:"), "");
+        let mut helper = test_util::helper();
+        helper.id_prefix = Some("fare".to_string());
+        assert_eq!(
+            dump(FluentDumpHelper::new(helper), "we"),
+            "welcome=Hello!;"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Block helper that iterates resolved messages.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render, Scope},
+};
+
+use serde_json::{Number, Value};
+
+use crate::{args, fluent::FluentHelper, message};
+
+static FIRST: &str = "first";
+static LAST: &str = "last";
+static KEY: &str = "key";
+static INDEX: &str = "index";
+
+/// Iterate the messages for a list of message ids.
+///
+/// Each argument is a message id and the hash parameters are passed as
+/// arguments to every message. The language is resolved once for the
+/// block and a catalog is not required; the id prefix is applied to
+/// each id and a namespaced id is resolved by its namespace loader.
+/// The `@key` variable is the message id as it was passed.
+///
+/// Each iteration sets the resolved message as the scope and the
+/// local variables `@first`, `@last`, `@index` and `@key` which
+/// is the message id; the scope is `null` for a missing message
+/// when the `on_missing` hash parameter is `null`.
+///
+/// ```text
+/// {{#fluent_each "save" "cancel" "delete"}}<button>{{this}}</button>{{/fluent_each}}
+/// ```
+pub struct FluentEachHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentEachHelper {
+    /// Create a new fluent each helper using a fluent helper
+    /// to resolve messages.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentEachHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..usize::MAX)?;
+        let template = ctx.assert_block(template)?;
+
+        let lang_id = self.helper.lang(rc, ctx)?;
+        let mut args = self.helper.arguments(rc, ctx, &lang_id, None)?;
+        self.helper.transform_args(&lang_id, &mut args);
+
+        let on_missing = message::on_missing(ctx)?;
+        let len = ctx.arguments().len();
+        let mut messages = Vec::with_capacity(len);
+        for index in 0..len {
+            let msg_id = args::msg_id_arg(ctx, index)?;
+            let message = self.helper.format_message(
+                ctx,
+                &lang_id,
+                &self.helper.prefixed(msg_id),
+                args.as_ref(),
+                on_missing,
+            )?;
+            messages.push((msg_id, message));
+        }
+
+        rc.push_scope(Scope::new());
+        for (index, (msg_id, message)) in messages.into_iter().enumerate() {
+            if let Some(ref mut scope) = rc.scope_mut() {
+                scope.set_local(FIRST, Value::Bool(index == 0));
+                scope.set_local(LAST, Value::Bool(index == len - 1));
+                scope.set_local(INDEX, Value::Number(Number::from(index)));
+                scope.set_local(KEY, Value::String(msg_id.to_string()));
+                scope
+                    .set_base_value(message.map_or(Value::Null, Value::String));
+            }
+            if let Err(e) = rc.template(template) {
+                rc.pop_scope();
+                return Err(e);
+            }
+        }
+        rc.pop_scope();
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{loader, render, with_namespace};
+
+    #[test]
+    fn each() {
+        let helper =
+            FluentEachHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{#fluent_each "welcome" "farewell" "help"}}<b>{{this}}</b>{{/fluent_each}}"#;
+        let result =
+            render("fluent_each", helper, template, json!({"lang": "de"}));
+        assert_eq!(
+            result.unwrap(),
+            "<b>Hallo!</b><b>Auf Wiedersehen!</b><b>Help</b>"
+        );
+    }
+
+    #[test]
+    fn missing_message() {
+        let helper =
+            FluentEachHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{#fluent_each "welcome" "nope" on_missing="null"}}{{@key}}={{#if this}}{{this}}{{else}}-{{/if}};{{/fluent_each}}"#;
+        let result =
+            render("fluent_each", helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "welcome=Bonjour!;nope=-;");
+    }
+
+    #[test]
+    fn namespaced() {
+        let mut helper = with_namespace();
+        helper.id_prefix = Some("fare".to_string());
+        let helper = FluentEachHelper::new(helper);
+        let template = r#"{{#fluent_each "well" "!single:welcome"}}{{@key}}={{this}};{{/fluent_each}}"#;
+        let result =
+            render("fluent_each", helper, template, json!({"lang": "de"}));
+        assert_eq!(
+            result.unwrap(),
+            "well=Auf Wiedersehen!;!single:welcome=مرحبا;"
+        );
+    }
+}
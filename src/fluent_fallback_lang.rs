@@ -0,0 +1,68 @@
+//! Helper to get the fallback language of a loader.
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use fluent_templates::LanguageIdentifier;
+
+/// Get the fallback language of a loader.
+///
+/// Returns the language tag of the language the loader falls back
+/// to when a message is missing so templates can show a notice when
+/// a translation is not available. The loaders do not expose the
+/// fallback language so it is given when the helper is created, for
+/// a `Catalog` use `Catalog::fallback()`.
+///
+/// ```text
+/// <p class="notice">Translation unavailable, showing {{fluent_fallback_lang}}</p>
+/// ```
+pub struct FluentFallbackLangHelper {
+    fallback: String,
+}
+
+impl FluentFallbackLangHelper {
+    /// Create a new fluent fallback language helper.
+    pub fn new(fallback: LanguageIdentifier) -> Self {
+        Self {
+            fallback: fallback.to_string(),
+        }
+    }
+}
+
+impl Helper for FluentFallbackLangHelper {
+    fn call<'render, 'call>(
+        &self,
+        _rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+        Ok(Some(Value::String(self.fallback.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{catalog, render};
+
+    #[test]
+    fn fallback_lang() {
+        let helper =
+            FluentFallbackLangHelper::new(catalog().fallback().clone());
+        let template = r#"{{fluent_fallback_lang}}"#;
+        let result = render(
+            "fluent_fallback_lang",
+            helper,
+            template,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(result.unwrap(), "en");
+    }
+}
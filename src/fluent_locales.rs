@@ -0,0 +1,72 @@
+//! Helper to list the available locales.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::fluent::FluentHelper;
+
+/// List the locales available from the loader of a fluent helper.
+///
+/// Returns an array of language identifier strings sorted
+/// so the order is stable; the locales of a reloaded loader
+/// are used after the loader is replaced.
+///
+/// ```text
+/// {{#each (fluent_locales)}}<a href="/{{this}}/">{{this}}</a>{{/each}}
+/// ```
+pub struct FluentLocalesHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentLocalesHelper {
+    /// Create a new fluent locales helper using the loader
+    /// of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentLocalesHelper {
+    fn call<'render, 'call>(
+        &self,
+        _rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+
+        let loader = self.helper.shared_loader().get();
+        let mut locales: Vec<String> =
+            loader.locales().map(|lang| lang.to_string()).collect();
+        locales.sort();
+
+        Ok(Some(Value::Array(
+            locales.into_iter().map(Value::String).collect(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{loader, render};
+
+    #[test]
+    fn locales() {
+        let helper =
+            FluentLocalesHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{#each (fluent_locales)}}{{this}} {{/each}}"#;
+        let result = render("fluent_locales", helper, template, json!({}));
+        assert_eq!(result.unwrap(), "de en fr ");
+    }
+}
@@ -0,0 +1,190 @@
+//! Helper to format numbers for the current language.
+use std::sync::Arc;
+
+use bracket::{
+    error::HelperError,
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render, Type},
+};
+
+use fluent_templates::fluent_bundle::types::FluentNumber;
+
+use crate::{fluent::FluentHelper, message, number};
+
+static TAGGED: &str = "tagged";
+
+/// Format a number for the current language.
+///
+/// Only the language of the fluent helper is resolved so a catalog is
+/// not required and the `lang` hash parameter selects another language;
+/// the `style`, `currency`, `useGrouping`, `minimumFractionDigits` and
+/// `maximumFractionDigits` hash parameters are the same as the
+/// options for the fluent `NUMBER()` function. The `number_format`
+/// function of the fluent helper is used before the default formatting,
+/// which groups the digits and uses the decimal separator of the
+/// language, and the number is escaped in the same way as a message.
+///
+/// A sub-expression with the `tagged` hash parameter returns the number
+/// as a tagged object so it is passed to a message as a number with
+/// the formatting options rather than as a formatted string.
+///
+/// ```text
+/// {{fluent_number 1234.567 maximumFractionDigits=1}}
+/// {{fluent "items" count=(fluent_number total minimumFractionDigits=1 tagged=true)}}
+/// ```
+pub struct FluentNumberHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentNumberHelper {
+    /// Create a new fluent number helper using the settings
+    /// of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentNumberHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let value =
+            ctx.try_get(0, &[Type::Number])?.as_f64().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' argument 0 must be a number",
+                    ctx.name()
+                ))
+            })?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let number = FluentNumber::new(value, number::options(ctx)?);
+
+        let tagged = if let Some(value) = ctx.param(TAGGED) {
+            value.as_bool().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' the 'tagged' parameter must be a boolean",
+                    ctx.name()
+                ))
+            })?
+        } else {
+            false
+        };
+        if tagged && message::is_sub_expression(ctx) {
+            return Ok(Some(number::to_tagged(&number)));
+        }
+
+        let message = number::format_with(
+            &number,
+            &lang_id,
+            self.helper.number_format.as_ref(),
+        );
+        message::write(
+            rc,
+            ctx,
+            message,
+            self.helper.escape,
+            self.helper.escape_mode,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket::Registry;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{self, loader};
+
+    fn render(helper: FluentHelper, template: &str) -> String {
+        render_lang(helper, template, "fr")
+    }
+
+    fn render_lang(helper: FluentHelper, template: &str, lang: &str) -> String {
+        let helper = FluentNumberHelper::new(helper);
+        test_util::render(
+            "fluent_number",
+            helper,
+            template,
+            json!({ "lang": lang }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn grouping() {
+        let helper = || FluentHelper::new(Box::new(loader()));
+        let template = r#"{{fluent_number 1234567.89}} {{fluent_number 1234567.89 useGrouping=false}}"#;
+        assert_eq!(
+            render_lang(helper(), template, "en"),
+            "1,234,567.89 1234567.89"
+        );
+        assert_eq!(
+            render_lang(helper(), template, "fr"),
+            "1\u{202f}234\u{202f}567,89 1234567,89"
+        );
+    }
+
+    #[test]
+    fn fraction_digits() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let template = r#"{{fluent_number 1234.567 maximumFractionDigits=2}} {{fluent_number 5 minimumFractionDigits=2}} {{fluent_number -0.001 maximumFractionDigits=2}}"#;
+        assert_eq!(render(helper, template), "1\u{202f}234,57 5,00 0");
+    }
+
+    #[test]
+    fn number_format() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .number_format(Box::new(|number, lang| {
+                if lang.language.as_str() == "fr" {
+                    Some(number.value.to_string().replace('.', ","))
+                } else {
+                    None
+                }
+            }))
+            .build();
+        assert_eq!(render(helper, "{{fluent_number 1.5}}"), "1,5");
+    }
+
+    #[test]
+    fn tagged() {
+        let mut registry = Registry::new();
+        let helper = FluentHelper::new(Box::new(loader()));
+        registry
+            .helpers_mut()
+            .insert("fluent_number", Box::new(FluentNumberHelper::new(helper)));
+        let helper = FluentHelper::new(Box::new(loader()));
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        let template = r#"{{fluent "items" count=(fluent_number 1 tagged=true)}}, {{fluent "items" count=(fluent_number 1 minimumFractionDigits=1 tagged=true)}}"#;
+        let result = registry.once("test", template, &json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "One item, 1.0 items");
+    }
+
+    #[test]
+    fn lang_param() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let template =
+            r#"{{fluent_number 1234.5}} {{fluent_number 1234.5 lang="de"}}"#;
+        assert_eq!(render(helper, template), "1\u{202f}234,5 1.234,5");
+        let helper =
+            FluentNumberHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = test_util::render(
+            "fluent_number",
+            helper,
+            "{{fluent_number 1}}",
+            json!({}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_number' requires a '@root.lang' variable in the template data"
+        );
+    }
+}
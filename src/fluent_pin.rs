@@ -0,0 +1,198 @@
+//! Block helper that resolves the language once for a block.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue, LocalHelper},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use crate::{fluent::FluentHelper, lang::Pinned};
+
+static FLUENT: &str = "fluent";
+
+/// Pin the language for the messages in a block.
+///
+/// The language is resolved once when the block starts, including the
+/// `lang` hash parameter of the block, and the `fluent` calls in the
+/// block use it without evaluating `lang_path` in the template data.
+/// The `lang` hash parameter of a call and a scoped language that
+/// differs from the root language still take precedence.
+///
+/// Inside the block the calls are resolved by the wrapped
+/// `FluentHelper` registered as a local helper named `lookup_helper`
+/// which is removed when the block ends so the pin never outlives the
+/// block. Content that a helper renders into a buffer does not see
+/// local helpers so calls in the buffer resolve the language as usual;
+/// blocks cannot be nested.
+///
+/// ```text
+/// {{#fluent_pin}}
+/// <h1>{{fluent "title"}}</h1>
+/// {{#each items}}<li>{{fluent "item" name=this}}</li>{{/each}}
+/// {{/fluent_pin}}
+/// ```
+pub struct FluentPinHelper {
+    helper: Arc<FluentHelper>,
+    /// Name of the local helper used to resolve messages in the block,
+    /// default is `fluent`.
+    pub lookup_helper: &'static str,
+}
+
+impl FluentPinHelper {
+    /// Create a new fluent pin helper using a fluent helper
+    /// to resolve messages.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            lookup_helper: FLUENT,
+        }
+    }
+}
+
+/// Local helper that resolves messages with the pinned language.
+#[derive(Clone)]
+struct PinLookup {
+    helper: Arc<FluentHelper>,
+    pinned: Arc<Pinned>,
+}
+
+impl Helper for PinLookup {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        self.helper.resolve_with(
+            rc,
+            ctx,
+            template,
+            self.helper.escape_mode,
+            None,
+            Some(&self.pinned),
+        )
+    }
+}
+
+impl LocalHelper for PinLookup {}
+
+impl Helper for FluentPinHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+        let template = ctx.assert_block(template)?;
+
+        let pinned = self.helper.pin(rc, ctx)?;
+        rc.register_local_helper(
+            self.lookup_helper,
+            Box::new(PinLookup {
+                helper: Arc::clone(&self.helper),
+                pinned: Arc::new(pinned),
+            }),
+        );
+
+        // Always unregister the local helper before propagating
+        // an error from the block so it does not leak
+        let result = rc.template(template);
+        rc.unregister_local_helper(self.lookup_helper);
+        result?;
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket::{helper::HelperValue, Registry};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::loader;
+
+    /// Block helper that writes the block rendered into a buffer.
+    struct Buffer;
+
+    impl Helper for Buffer {
+        fn call<'render, 'call>(
+            &self,
+            rc: &mut Render<'render>,
+            ctx: &Context<'call>,
+            template: Option<&'render Node<'render>>,
+        ) -> HelperValue {
+            let content = rc.buffer(ctx.assert_block(template)?)?;
+            rc.write(&content)?;
+            Ok(None)
+        }
+    }
+
+    /// Helper that renders a template with other data.
+    struct Nested;
+
+    impl Helper for Nested {
+        fn call<'render, 'call>(
+            &self,
+            rc: &mut Render<'render>,
+            _ctx: &Context<'call>,
+            _template: Option<&'render Node<'render>>,
+        ) -> HelperValue {
+            let data = json!({"lang": "de"});
+            let content =
+                rc.once("nested", r#"{{fluent "welcome"}}"#, &data)?;
+            rc.write(&content)?;
+            Ok(None)
+        }
+    }
+
+    fn registry() -> Registry<'static> {
+        let mut registry = Registry::new();
+        crate::register_all(
+            &mut registry,
+            FluentHelper::new(Box::new(loader())),
+        );
+        registry.helpers_mut().insert("buffer", Box::new(Buffer));
+        registry.helpers_mut().insert("nested", Box::new(Nested));
+        registry
+    }
+
+    #[test]
+    fn pin_lang() {
+        let registry = registry();
+        let template = r#"{{#fluent_pin}}{{fluent "welcome"}} {{fluent "welcome" lang="de"}} {{#with german}}{{fluent "welcome"}}{{/with}} {{fluent "welcome"}}{{/fluent_pin}} {{fluent "welcome"}}"#;
+        let data = json!({"lang": "en", "german": {"lang": "de"}});
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "Hello! Hallo! Hallo! Hello! Hello!");
+        let data = json!({"lang": "fr", "german": {"lang": "de"}});
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "Bonjour! Hallo! Hallo! Bonjour! Bonjour!");
+    }
+
+    #[test]
+    fn block_lang() {
+        let registry = registry();
+        let template = r#"{{#fluent_pin lang="de"}}{{fluent "welcome"}}{{/fluent_pin}} {{fluent "welcome"}}"#;
+        let result = registry.once("test", template, &json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hallo! Hello!");
+    }
+
+    #[test]
+    fn nested_render() {
+        let registry = registry();
+        let template = r#"{{#fluent_pin}}{{fluent "welcome"}} {{nested}} {{fluent "welcome"}}{{/fluent_pin}}"#;
+        let result = registry.once("test", template, &json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Bonjour! Hallo! Bonjour!");
+    }
+
+    #[test]
+    fn buffered_render() {
+        let registry = registry();
+        let template = r#"{{#fluent_pin}}{{#buffer}}{{fluent "welcome"}} {{#with german}}{{fluent "welcome"}}{{/with}}{{/buffer}} {{fluent "welcome"}}{{/fluent_pin}}"#;
+        let data = json!({"lang": "fr", "german": {"lang": "de"}});
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "Bonjour! Hallo! Bonjour!");
+    }
+}
@@ -0,0 +1,168 @@
+//! Helper to resolve a message for a count.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bracket::{
+    error::HelperError,
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render, Type},
+};
+
+use serde_json::Value;
+
+use fluent_templates::fluent_bundle::FluentValue;
+
+use crate::{
+    args::{self, COUNT},
+    fluent::FluentHelper,
+    message,
+};
+
+/// Resolve a message with a count for the current language.
+///
+/// The first argument is the message id and the second argument
+/// is the count which is always passed to the message as a number
+/// so that it selects the plural category for the language; the
+/// count takes precedence over a hash parameter with the same name.
+///
+/// A catalog is not required; the message is resolved by the loader,
+/// or by the namespace loader for a namespaced message id, using the
+/// language fallbacks of the fluent helper. A missing message writes
+/// the missing text of the fluent helper unless the `on_missing` hash
+/// parameter is set, so `on_missing="null"` in a sub-expression returns
+/// `null` for a missing message.
+///
+/// ```text
+/// {{fluent_plural "items-count" cart.items.length}}
+/// ```
+pub struct FluentPluralHelper {
+    helper: Arc<FluentHelper>,
+    /// Name of the count variable, default is `count`.
+    pub count_arg: String,
+}
+
+impl FluentPluralHelper {
+    /// Create a new fluent plural helper using a fluent helper
+    /// to resolve messages.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            count_arg: COUNT.to_string(),
+        }
+    }
+}
+
+impl Helper for FluentPluralHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(2..2)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let count =
+            ctx.try_get(1, &[Type::Number])?.as_f64().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error in helper '{}' argument 1 must be a number",
+                    ctx.name()
+                ))
+            })?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let mut args = self.helper.arguments(rc, ctx, &lang_id, None)?;
+        args.get_or_insert_with(HashMap::new)
+            .insert(self.count_arg.clone(), FluentValue::from(count));
+        self.helper.transform_args(&lang_id, &mut args);
+
+        let message = self.helper.format_message(
+            ctx,
+            &lang_id,
+            &msg_id,
+            args.as_ref(),
+            message::on_missing(ctx)?,
+        )?;
+        match message {
+            Some(message) => self.helper.write(
+                rc,
+                ctx,
+                &msg_id,
+                message,
+                self.helper.escape_mode,
+            ),
+            None if message::is_sub_expression(ctx) => Ok(Some(Value::Null)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{loader, render, with_namespace};
+
+    #[test]
+    fn plural() {
+        let helper =
+            FluentPluralHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = render(
+            "fluent_plural",
+            helper,
+            r#"{{fluent_plural "items" 1}}, {{fluent_plural "items" 3}}"#,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(result.unwrap(), "Un article, 3 articles");
+    }
+
+    #[test]
+    fn default_lang() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .default_lang(langid!("en"))
+            .build();
+        let helper = FluentPluralHelper::new(helper);
+        let result = render(
+            "fluent_plural",
+            helper,
+            r#"{{fluent_plural "items" 1}}"#,
+            json!({}),
+        );
+        assert_eq!(result.unwrap(), "One item");
+    }
+
+    #[test]
+    fn plural_categories() {
+        let template = r#"{{fluent_plural "items" 0}}, {{fluent_plural "items" 1}}, {{fluent_plural "items" 5}}"#;
+        let render = |lang: &str| {
+            let helper =
+                FluentPluralHelper::new(FluentHelper::new(Box::new(loader())));
+            render("fluent_plural", helper, template, json!({ "lang": lang }))
+                .unwrap()
+        };
+        assert_eq!(render("en"), "0 items, One item, 5 items");
+        assert_eq!(render("fr"), "Un article, Un article, 5 articles");
+    }
+
+    #[test]
+    fn missing_message() {
+        let helper =
+            FluentPluralHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"[{{fluent_plural "nope" 1}}] [{{fluent_plural "nope" 1 on_missing="empty"}}] [{{#if (fluent_plural "nope" 1 on_missing="null")}}found{{/if}}]"#;
+        let result =
+            render("fluent_plural", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "[Unknown localization nope] [] []");
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentPluralHelper::new(with_namespace());
+        let template = r#"{{fluent_plural "single:welcome" 1}} {{fluent_plural "items" 1}}"#;
+        let result =
+            render("fluent_plural", helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "مرحبا Un article");
+    }
+}
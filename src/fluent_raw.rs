@@ -0,0 +1,62 @@
+//! Helper for message lookup that does not escape messages.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use crate::{escape::EscapeMode, fluent::FluentHelper};
+
+/// Lookup a language string without escaping the message.
+///
+/// Messages are resolved in the same way as the wrapped
+/// `FluentHelper` but are always written without escaping,
+/// the `escape` hash parameter is ignored.
+///
+/// ```text
+/// {{fluent_raw "banner-html"}}
+/// ```
+pub struct FluentRawHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentRawHelper {
+    /// Create a new fluent raw helper using a fluent helper
+    /// to resolve messages.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentRawHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        self.helper.resolve(rc, ctx, template, EscapeMode::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{loader, render};
+
+    #[test]
+    fn raw() {
+        let helper =
+            FluentRawHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{fluent_raw "raw-html"}}"#;
+        let result =
+            render("fluent_raw", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "<b>Bold</b>");
+    }
+}
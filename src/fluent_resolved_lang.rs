@@ -0,0 +1,87 @@
+//! Helper to find the language used to resolve a message.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{args, fluent::FluentHelper};
+
+/// Find the language used to resolve a message.
+///
+/// The requested language is tried first followed by the `fallbacks`
+/// of the shared `FluentHelper` in order; each language is checked in
+/// the catalog of the fluent helper on its own and the first language
+/// that has the message is returned. When no language has the message
+/// the fallback language of the catalog is returned if it has the
+/// message, otherwise the requested language is returned.
+///
+/// Without a catalog, and for namespaced message ids, the loader formats
+/// the message and answers from its own fallback languages so the first
+/// language of the chain that resolves the message is returned.
+///
+/// ```text
+/// <span data-lang="{{fluent_resolved_lang "welcome"}}">{{fluent "welcome"}}</span>
+/// ```
+pub struct FluentResolvedLangHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentResolvedLangHelper {
+    /// Create a new fluent resolved language helper using the
+    /// settings and catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentResolvedLangHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let resolved = self
+            .helper
+            .message_lang(rc, ctx, &lang_id, &msg_id)?
+            .unwrap_or(lang_id);
+
+        Ok(Some(Value::String(resolved.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{helper, render};
+
+    #[test]
+    fn resolved_lang() {
+        let mut helper = helper();
+        helper.fallbacks = vec![langid!("de")];
+        let helper = FluentResolvedLangHelper::new(helper);
+        let template = r#"{{fluent_resolved_lang "welcome"}} {{fluent_resolved_lang "farewell"}} {{fluent_resolved_lang "help"}} {{fluent_resolved_lang "nope"}}"#;
+        let result = render(
+            "fluent_resolved_lang",
+            helper,
+            template,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(result.unwrap(), "fr de en fr");
+    }
+}
@@ -0,0 +1,175 @@
+//! Block helper that shares block parameters between messages.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bracket::{
+    error::HelperError,
+    helper::{Helper, HelperValue, LocalHelper},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use crate::fluent::{FluentHelper, FluentParam, Parameters};
+
+static FLUENT: &str = "fluent";
+
+/// Share block parameters between the messages in a block.
+///
+/// The `fluentparam` blocks inside the block capture parameters
+/// that are passed to every message resolved by the `fluent` calls
+/// in the rest of the block; hash parameters and the block parameters
+/// of a call take precedence. The parameters are discarded when the
+/// block ends so they are not available to other blocks.
+///
+/// Inside the block the calls are resolved by the wrapped
+/// `FluentHelper` registered as a local helper named `lookup_helper`;
+/// blocks cannot be nested.
+///
+/// ```text
+/// {{#fluent_set}}
+/// {{~#fluentparam "name"}}{{user.first}} {{user.last}}{{/fluentparam~}}
+/// <h1>{{fluent "greeting"}}</h1>
+/// <p>{{fluent "farewell"}}</p>
+/// {{/fluent_set}}
+/// ```
+pub struct FluentSetHelper {
+    helper: Arc<FluentHelper>,
+    /// Name of the local helper used to resolve messages in the block,
+    /// default is `fluent`.
+    pub lookup_helper: &'static str,
+}
+
+impl FluentSetHelper {
+    /// Create a new fluent set helper using a fluent helper
+    /// to resolve messages.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            lookup_helper: FLUENT,
+        }
+    }
+}
+
+/// Local helper that resolves messages with the shared parameters.
+#[derive(Clone)]
+struct SetLookup {
+    helper: Arc<FluentHelper>,
+    parameters: Parameters,
+}
+
+impl Helper for SetLookup {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        let captured = self
+            .parameters
+            .lock()
+            .map_err(|_| {
+                HelperError::new(format!(
+                    "Helper '{}' failed to lock the parameters",
+                    ctx.name()
+                ))
+            })?
+            .clone();
+        let mut extra = HashMap::with_capacity(captured.len());
+        self.helper.insert_captured(&mut extra, captured);
+
+        let result = self.helper.resolve_with(
+            rc,
+            ctx,
+            template,
+            self.helper.escape_mode,
+            Some(&extra),
+            None,
+        );
+
+        // A block call replaces the block parameter helper so
+        // restore it for the rest of the set
+        if template.is_some() {
+            rc.register_local_helper(
+                self.helper.param_helper,
+                Box::new(FluentParam::new(Arc::clone(&self.parameters))),
+            );
+        }
+
+        result
+    }
+}
+
+impl LocalHelper for SetLookup {}
+
+impl Helper for FluentSetHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+        let template = ctx.assert_block(template)?;
+
+        let parameters: Parameters = Default::default();
+        rc.register_local_helper(
+            self.helper.param_helper,
+            Box::new(FluentParam::new(Arc::clone(&parameters))),
+        );
+        rc.register_local_helper(
+            self.lookup_helper,
+            Box::new(SetLookup {
+                helper: Arc::clone(&self.helper),
+                parameters,
+            }),
+        );
+
+        // Always unregister the local helpers before propagating
+        // an error from the block so they do not leak
+        let result = rc.template(template);
+        rc.unregister_local_helper(self.helper.param_helper);
+        rc.unregister_local_helper(self.lookup_helper);
+        result?;
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket::Registry;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::loader;
+
+    #[test]
+    fn shared_parameters() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let mut registry = Registry::new();
+        registry
+            .helpers_mut()
+            .insert("fluent_set", Box::new(FluentSetHelper::new(helper)));
+        let helper = FluentHelper::new(Box::new(loader()));
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        let template = r#"{{#fluent_set}}{{#fluentparam "name"}}Ann{{/fluentparam}}{{fluent "greeting"}} {{fluent "receipt" count=2 currency="EUR"}}{{/fluent_set}} {{fluent "greeting" name="Bob"}}"#;
+        let result = registry.once("test", template, &json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann! Ann bought 2 EUR Hello Bob!");
+    }
+
+    #[test]
+    fn partial_content() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let mut registry = Registry::new();
+        registry
+            .helpers_mut()
+            .insert("fluent_set", Box::new(FluentSetHelper::new(helper)));
+        let helper = FluentHelper::new(Box::new(loader()));
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        registry.insert("card", r#"{{fluent "card"}}"#).unwrap();
+        let template = r#"{{#fluent_set}}{{~#fluentparam "body"}}<b>{{name}}</b>{{/fluentparam~}}{{> card}}{{/fluent_set}}"#;
+        let data = json!({"lang": "en", "name": "Ann"});
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "Card: &lt;b&gt;Ann&lt;/b&gt;");
+    }
+}
@@ -0,0 +1,166 @@
+//! Helper to resolve a message and return formatting errors.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{args, catalog, fluent::FluentHelper};
+
+static VALUE: &str = "value";
+static ERRORS: &str = "errors";
+
+/// Resolve a message in a catalog and return any errors.
+///
+/// Returns an object with the formatted message assigned to `value`
+/// and an array of error descriptions assigned to `errors`; when the
+/// message cannot be found `value` is `null`. The hash parameters are
+/// passed as arguments and formatting errors, such as a missing
+/// variable, are reported instead of being logged.
+///
+/// The fluent helper must have a catalog because a `Loader` does not
+/// return the errors. The message is read from the first language of
+/// the locale fallbacks, or the fallback language of the catalog, that
+/// has the message; a namespaced message id must be in the catalog as
+/// it is and is otherwise reported as an unknown message.
+///
+/// ```text
+/// {{#each (lookup (fluent_try "greeting") "errors")}}{{this}}{{/each}}
+/// ```
+pub struct FluentTryHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl FluentTryHelper {
+    /// Create a new fluent try helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for FluentTryHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let msg_id = msg_id.as_ref();
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let mut args = self.helper.arguments(rc, ctx, &lang_id, None)?;
+        self.helper.transform_args(&lang_id, &mut args);
+
+        let (value, errors) = match catalog.message_value(&lang_id, msg_id) {
+            Some((bundle, pattern)) => {
+                let (value, errors) =
+                    catalog::try_format_pattern(bundle, pattern, args.as_ref());
+                let value = self.helper.isolate(value);
+                let errors = errors
+                    .iter()
+                    .map(|error| Value::String(catalog::describe_error(error)))
+                    .collect();
+                (Value::String(value), errors)
+            }
+            None => (
+                Value::Null,
+                vec![Value::String(format!(
+                    "Unknown message: {} for language {}",
+                    msg_id, lang_id
+                ))],
+            ),
+        };
+
+        let mut result = Map::new();
+        result.insert(VALUE.to_string(), value);
+        result.insert(ERRORS.to_string(), Value::Array(errors));
+        Ok(Some(Value::Object(result)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{
+        self, helper, loader, render, stripped, with_namespace,
+    };
+
+    fn try_render(helper: FluentHelper, msg_id: &str) -> String {
+        let template = format!(
+            r#"{{{{#with (fluent_try "{}")}}}}{{{{value}}}}|{{{{#each errors}}}}{{{{this}}}};{{{{/each}}}}{{{{/with}}}}"#,
+            msg_id
+        );
+        let helper = FluentTryHelper::new(helper);
+        render("fluent_try", helper, &template, json!({"lang": "en"})).unwrap()
+    }
+
+    #[test]
+    fn errors() {
+        let template = r#"{{#with (fluent_try "greeting")}}{{value}}|{{#each errors}}{{this}};{{/each}}{{/with}}"#;
+        let helper = FluentTryHelper::new(helper());
+        let result =
+            render("fluent_try", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello {$name}!|Unknown variable: $name;");
+
+        let template = r#"{{#with (fluent_try "greeting" name="Ann")}}{{value}}|{{#each errors}}{{this}};{{/each}}{{/with}}"#;
+        let helper = FluentTryHelper::new(test_util::helper());
+        let result =
+            render("fluent_try", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann!|");
+    }
+
+    #[test]
+    fn isolating() {
+        let helper = FluentTryHelper::new(stripped());
+        let template =
+            r#"{{#with (fluent_try "greeting" name="Ann")}}{{value}}{{/with}}"#;
+        let result =
+            render("fluent_try", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann!");
+    }
+
+    #[test]
+    fn missing_message() {
+        assert_eq!(
+            try_render(helper(), "nope"),
+            "null|Unknown message: nope for language en;"
+        );
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentTryHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = render(
+            "fluent_try",
+            helper,
+            r#"{{fluent_try "welcome"}}"#,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_try' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        assert_eq!(
+            try_render(with_namespace(), "single:welcome"),
+            "null|Unknown message: single:welcome for language en;"
+        );
+    }
+}
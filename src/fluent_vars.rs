@@ -0,0 +1,156 @@
+//! Helper to list the variables referenced by a message.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{args, error::FluentError, fluent::FluentHelper, vars};
+
+/// List the variables referenced by a message in a catalog.
+///
+/// Returns an array of variable names in the order they are first
+/// referenced; when `attributes` is set the variables referenced by
+/// the message attributes are also included. Variables referenced by
+/// terms are not included because a term has its own arguments.
+///
+/// The fluent helper must have a catalog to read the message syntax.
+/// The variables are read from the first language of the locale
+/// fallbacks, or the fallback language of the catalog, that has the
+/// message; it is an error when no language has the message and a
+/// namespaced message id must be in the catalog as it is.
+///
+/// ```text
+/// {{#each (fluent_vars "greeting")}}<input name="{{this}}">{{/each}}
+/// ```
+pub struct FluentVarsHelper {
+    helper: Arc<FluentHelper>,
+    /// Include the variables referenced by attributes, default is `false`.
+    pub attributes: bool,
+}
+
+impl FluentVarsHelper {
+    /// Create a new fluent variables helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+            attributes: false,
+        }
+    }
+}
+
+impl Helper for FluentVarsHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let msg_id = msg_id.as_ref();
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let message = catalog
+            .find(&lang_id, msg_id)
+            .and_then(|bundle| bundle.get_message(msg_id))
+            .ok_or_else(|| {
+                FluentError::unknown_message(ctx.name(), msg_id, &lang_id)
+            })?;
+
+        let mut names = Vec::new();
+        if let Some(pattern) = message.value {
+            vars::pattern_variables(pattern, &mut names);
+        }
+        if self.attributes {
+            let mut attributes: Vec<_> = message.attributes.iter().collect();
+            attributes.sort_by_key(|(name, _)| *name);
+            for (_, pattern) in attributes {
+                vars::pattern_variables(pattern, &mut names);
+            }
+        }
+
+        Ok(Some(Value::Array(
+            names
+                .into_iter()
+                .map(|name| Value::String(name.to_string()))
+                .collect(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{self, helper, loader, render, with_namespace};
+
+    #[test]
+    fn variables() {
+        let template = r#"{{#each (fluent_vars "signup")}}{{this}} {{/each}}"#;
+        let helper = FluentVarsHelper::new(helper());
+        let result =
+            render("fluent_vars", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "name ");
+
+        let mut helper = FluentVarsHelper::new(test_util::helper());
+        helper.attributes = true;
+        let result =
+            render("fluent_vars", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "name email ");
+    }
+
+    #[test]
+    fn missing_message() {
+        let helper = FluentVarsHelper::new(helper());
+        let result = render(
+            "fluent_vars",
+            helper,
+            r#"{{fluent_vars "nope"}}"#,
+            json!({"lang": "fr"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_vars' could not find message 'nope' for language 'fr'"
+        );
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            FluentVarsHelper::new(FluentHelper::new(Box::new(loader())));
+        let result = render(
+            "fluent_vars",
+            helper,
+            r#"{{fluent_vars "greeting"}}"#,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_vars' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = FluentVarsHelper::new(with_namespace());
+        let result = render(
+            "fluent_vars",
+            helper,
+            r#"{{fluent_vars "single:greeting"}}"#,
+            json!({"lang": "en"}),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'fluent_vars' could not find message 'single:greeting' for language 'en'"
+        );
+    }
+}
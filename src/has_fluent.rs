@@ -0,0 +1,123 @@
+//! Helper to determine if a message exists.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{args, fluent::FluentHelper};
+
+/// Determine if a message exists for the current language.
+///
+/// Returns a boolean; the message is looked up in the catalog of the
+/// fluent helper for the current language, each of the `fallbacks` and
+/// then the fallback language of the catalog without formatting it, so
+/// a message that fails to format still exists.
+///
+/// The `Loader` trait can only determine if a message exists by
+/// formatting it; without a catalog, and for namespaced message ids,
+/// the message is formatted by the loader with the hash parameters as
+/// arguments so pass any variables the message requires. Terms are
+/// always checked in the catalog and are an error without one.
+///
+/// ```text
+/// {{#if (has_fluent "promo-banner")}}{{fluent "promo-banner"}}{{/if}}
+/// ```
+pub struct HasFluentHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl HasFluentHelper {
+    /// Create a new has fluent helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for HasFluentHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(1..1)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let exists = self
+            .helper
+            .message_lang(rc, ctx, &lang_id, &msg_id)?
+            .is_some();
+        Ok(Some(Value::Bool(exists)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render, with_namespace};
+
+    #[test]
+    fn has_fluent() {
+        let helper =
+            HasFluentHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{#if (has_fluent "welcome")}}a{{/if}}{{#if (has_fluent "nope")}}b{{/if}}{{#if (has_fluent "greeting" name="Ann")}}c{{/if}}"#;
+        let result =
+            render("has_fluent", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "ac");
+    }
+
+    #[test]
+    fn without_formatting() {
+        let helper = HasFluentHelper::new(helper());
+        let template = r#"{{has_fluent "broken"}} {{has_fluent "login" lang="fr"}} {{has_fluent "nope" lang="fr"}}"#;
+        let result =
+            render("has_fluent", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "true true false");
+    }
+
+    #[test]
+    fn default_lang() {
+        let mut helper = helper();
+        helper.default_lang = Some(langid!("en"));
+        let helper = HasFluentHelper::new(helper);
+        let result = render(
+            "has_fluent",
+            helper,
+            r#"{{has_fluent "welcome"}}"#,
+            json!({}),
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            HasFluentHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{has_fluent "welcome"}} {{has_fluent "nope"}} {{has_fluent "greeting" name="Ann"}} {{has_fluent "login" lang="fr"}}"#;
+        let result =
+            render("has_fluent", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "true false true true");
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = HasFluentHelper::new(with_namespace());
+        let template = r#"{{has_fluent "single:farewell"}} {{has_fluent "single:nope"}} {{has_fluent "farewell"}}"#;
+        let result =
+            render("has_fluent", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "true false true");
+    }
+}
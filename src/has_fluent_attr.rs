@@ -0,0 +1,109 @@
+//! Helper to determine if a message has an attribute.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{args, fluent::FluentHelper};
+
+/// Determine if a message in a catalog has an attribute.
+///
+/// The first argument is the message id and the second
+/// argument is the name of the attribute; returns a boolean.
+///
+/// The fluent helper must have a catalog. The attribute is checked on
+/// the message in the first language of the locale fallbacks, or the
+/// fallback language of the catalog, that has the message; a missing
+/// message is `false` rather than an error and a namespaced message id
+/// is only found when it is in the catalog as it is.
+///
+/// ```text
+/// {{#if (has_fluent_attr "login" "title")}}{{fluent_attr "login" "title"}}{{/if}}
+/// ```
+pub struct HasFluentAttrHelper {
+    helper: Arc<FluentHelper>,
+}
+
+impl HasFluentAttrHelper {
+    /// Create a new has fluent attribute helper using the settings and
+    /// catalog of a fluent helper.
+    pub fn new<H: Into<Arc<FluentHelper>>>(helper: H) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+}
+
+impl Helper for HasFluentAttrHelper {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(2..2)?;
+
+        let msg_id = self.helper.prefixed(args::msg_id_arg(ctx, 0)?);
+        let attr_name = args::string_arg(ctx, 1)?;
+        let catalog = self.helper.require_catalog(ctx)?;
+        let lang_id = self.helper.lang(rc, ctx)?;
+
+        let exists = catalog
+            .message_attribute(&lang_id, &msg_id, attr_name)
+            .is_some();
+        Ok(Some(Value::Bool(exists)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{helper, loader, render, with_namespace};
+
+    #[test]
+    fn attribute() {
+        let helper = HasFluentAttrHelper::new(helper());
+        let template = r#"{{has_fluent_attr "login" "title"}} {{has_fluent_attr "login" "help"}} {{has_fluent_attr "nope" "title"}}"#;
+        let result =
+            render("has_fluent_attr", helper, template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "true false false");
+    }
+
+    #[test]
+    fn fallback_lang() {
+        let helper = HasFluentAttrHelper::new(helper());
+        let template = r#"{{has_fluent_attr "login" "title"}} {{has_fluent_attr "welcome" "title"}}"#;
+        let result =
+            render("has_fluent_attr", helper, template, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "true false");
+    }
+
+    #[test]
+    fn without_catalog() {
+        let helper =
+            HasFluentAttrHelper::new(FluentHelper::new(Box::new(loader())));
+        let template = r#"{{has_fluent_attr "login" "title"}}"#;
+        let result =
+            render("has_fluent_attr", helper, template, json!({"lang": "en"}));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Helper 'has_fluent_attr' requires a fluent helper with a catalog"
+        );
+    }
+
+    #[test]
+    fn namespaced() {
+        let helper = HasFluentAttrHelper::new(with_namespace());
+        let template = r#"{{has_fluent_attr "single:login" "title"}}"#;
+        let result =
+            render("has_fluent_attr", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "false");
+    }
+}
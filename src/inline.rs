@@ -0,0 +1,97 @@
+//! Format inline content as a fluent pattern.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bracket::{error::HelperError, helper::HelperResult};
+
+use fluent_templates::fluent_bundle::{FluentResource, FluentValue};
+use fluent_templates::LanguageIdentifier;
+
+use crate::catalog::{self, CatalogBundle};
+
+/// Message id used for the pseudo-message.
+static INLINE_ID: &str = "inline";
+
+/// Parse content as a fluent pattern and format it with the arguments.
+///
+/// The message id is only used to identify the pattern in log messages.
+/// Continuation lines are indented so that multi-line content
+/// parses as a single message value; the bundle only inserts unicode
+/// isolation marks when `isolating` is set.
+pub(crate) fn format(
+    name: &str,
+    msg_id: &str,
+    content: &str,
+    lang: &LanguageIdentifier,
+    args: Option<&HashMap<String, FluentValue<'static>>>,
+    isolating: bool,
+) -> HelperResult<String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Ok(String::new());
+    }
+
+    let source =
+        format!("{} = {}\n", INLINE_ID, content.replace('\n', "\n    "));
+    let resource = FluentResource::try_new(source).map_err(|(_, errors)| {
+        HelperError::new(format!(
+            "Helper '{}' could not parse inline pattern: {:?}",
+            name, errors
+        ))
+    })?;
+
+    let mut bundle = CatalogBundle::new(std::slice::from_ref(lang));
+    bundle.set_use_isolating(isolating);
+    bundle.add_resource(Arc::new(resource)).map_err(|errors| {
+        HelperError::new(format!(
+            "Helper '{}' could not add inline pattern: {:?}",
+            name, errors
+        ))
+    })?;
+
+    let pattern = bundle
+        .get_message(INLINE_ID)
+        .and_then(|message| message.value)
+        .ok_or_else(|| {
+            HelperError::new(format!(
+                "Helper '{}' inline pattern has no value",
+                name
+            ))
+        })?;
+
+    Ok(catalog::format_pattern(
+        &bundle, pattern, args, msg_id, lang,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::test_util::{fluent, loader};
+    use crate::FluentHelper;
+
+    #[test]
+    fn inline_pattern() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .isolating(false)
+            .build();
+        let template = r#"{{#fluent inline=true name="Ann"}}{{#fluentparam "count"}}3{{/fluentparam}}Hello { $name }, { $count } new{{/fluent}}"#;
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann, 3 new");
+    }
+
+    #[test]
+    fn isolating() {
+        let template =
+            r#"{{#fluent inline=true name="Ann"}}Hello { $name }{{/fluent}}"#;
+        let helper = FluentHelper::new(Box::new(loader()));
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello \u{2068}Ann\u{2069}");
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .isolating(false)
+            .build();
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello Ann");
+    }
+}
@@ -0,0 +1,557 @@
+//! Resolve the language for a helper call.
+use bracket::{
+    error::HelperError,
+    helper::HelperResult,
+    render::{Context, Render},
+};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use serde_json::Value;
+
+use fluent_templates::{LanguageIdentifier, Loader};
+
+use crate::error::FluentError;
+
+pub(crate) static LANG: &str = "lang";
+pub(crate) static LANG_PATH: &str = "@root.lang";
+
+static ROOT_PREFIX: &str = "@root.";
+static THIS_PREFIX: &str = "this.";
+
+/// Maximum number of parsed language identifiers to cache.
+const CACHE_CAPACITY: usize = 64;
+
+/// Cache of parsed language identifiers keyed by the raw string.
+///
+/// Once the cache is full new identifiers are parsed
+/// on every call but are not stored.
+#[derive(Default)]
+pub(crate) struct LangCache {
+    entries: RwLock<HashMap<String, LanguageIdentifier>>,
+}
+
+impl LangCache {
+    /// Parse a language identifier using the cache.
+    pub(crate) fn parse(&self, lang: &str) -> HelperResult<LanguageIdentifier> {
+        if let Ok(entries) = self.entries.read() {
+            if let Some(lang_id) = entries.get(lang) {
+                return Ok(lang_id.clone());
+            }
+        }
+
+        let lang_id = normalize(lang)
+            .parse::<LanguageIdentifier>()
+            .map_err(|e| FluentError::Parse(e.to_string()))?;
+
+        if let Ok(mut entries) = self.entries.write() {
+            if entries.len() < CACHE_CAPACITY {
+                entries.insert(lang.to_string(), lang_id.clone());
+            }
+        }
+
+        Ok(lang_id)
+    }
+
+    /// Add a language identifier to the cache.
+    pub(crate) fn insert(&self, lang_id: &LanguageIdentifier) {
+        if let Ok(mut entries) = self.entries.write() {
+            if entries.len() < CACHE_CAPACITY {
+                entries.insert(lang_id.to_string(), lang_id.clone());
+            }
+        }
+    }
+}
+
+/// Convert a POSIX locale such as `en_US.UTF-8` or `de_DE@euro`
+/// to a language identifier by removing the codeset and modifier
+/// and replacing underscores with hyphens.
+fn normalize(lang: &str) -> Cow<'_, str> {
+    if !lang.contains('_') {
+        return Cow::Borrowed(lang);
+    }
+    let end = lang.find(&['.', '@'][..]).unwrap_or(lang.len());
+    Cow::Owned(lang[..end].replace('_', "-"))
+}
+
+/// Get the language identifier string for a value.
+///
+/// The value may be a string or an object with a `language` field
+/// and optional `script` and `region` fields.
+fn to_lang_str(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(lang) => Some(Cow::Borrowed(lang)),
+        Value::Object(fields) => {
+            let mut lang = fields.get("language")?.as_str()?.to_string();
+            for key in &["script", "region"] {
+                if let Some(value) = fields.get(*key) {
+                    lang.push('-');
+                    lang.push_str(value.as_str()?);
+                }
+            }
+            Some(Cow::Owned(lang))
+        }
+        _ => None,
+    }
+}
+
+/// Get the path to look for a language in the current scope.
+///
+/// Only paths relative to `@root` have a scoped equivalent,
+/// `@root.lang` is looked up as `this.lang`.
+fn scoped_path(lang_path: &str) -> Option<String> {
+    lang_path
+        .strip_prefix(ROOT_PREFIX)
+        .map(|path| format!("{}{}", THIS_PREFIX, path))
+}
+
+/// Settings used to resolve the language identifier for a call.
+pub(crate) struct LangSettings<'a> {
+    /// Path used to find the language identifier in the template data.
+    pub lang_path: &'a str,
+    /// Cache of parsed language identifiers.
+    pub cache: &'a LangCache,
+    /// Language used when no language is found.
+    pub default: Option<&'a LanguageIdentifier>,
+    /// Loader used to negotiate a list of languages.
+    pub accept: Option<&'a dyn Loader>,
+    /// Language identifiers for aliases such as `english`.
+    pub aliases: Option<&'a HashMap<String, LanguageIdentifier>>,
+    /// Loader whose locale is used when no language or default is
+    /// found and the loader has exactly one locale.
+    pub single_locale: &'a dyn Loader,
+}
+
+/// Resolve the language identifier for a helper call.
+///
+/// The language is found in order of precedence from:
+///
+/// 1. The `lang` hash parameter unless it is `null`, which happens
+///    when the parameter is a path that does not exist.
+/// 2. The field of `lang_path` in the current scope, so that
+///    `lang` in a `with` or `each` block overrides `@root.lang`.
+/// 3. The value found at `lang_path` in the template data.
+///
+/// Without a language the default language is used, then the locale
+/// of a loader with a single locale, otherwise the error for a missing
+/// language is returned. When a loader is given to accept a list of
+/// languages a value in the `Accept-Language` format is negotiated
+/// against the locales of the loader. A language found in the aliases
+/// is used without parsing, other values are parsed as usual.
+pub(crate) fn resolve_with(
+    rc: &Render<'_>,
+    ctx: &Context<'_>,
+    settings: &LangSettings<'_>,
+) -> HelperResult<LanguageIdentifier> {
+    resolve_root(rc, ctx, settings, None)
+}
+
+/// Resolve the language identifier for a helper call in the same
+/// way as `resolve_with()` using a language pinned by a block.
+///
+/// The value at `lang_path` in the root data is not evaluated; the
+/// `lang` hash parameter and a scoped language that differs from the
+/// pinned root value still take precedence.
+pub(crate) fn resolve_pinned(
+    rc: &Render<'_>,
+    ctx: &Context<'_>,
+    settings: &LangSettings<'_>,
+    pinned: &Pinned,
+) -> HelperResult<LanguageIdentifier> {
+    resolve_root(rc, ctx, settings, Some(pinned))
+}
+
+/// Resolve the language identifier to pin for a block.
+///
+/// The language is resolved for the block call and the value at
+/// `lang_path` in the root data is kept so that the scoped path,
+/// which resolves to the same value at the root, is not treated
+/// as an override.
+pub(crate) fn pin(
+    rc: &Render<'_>,
+    ctx: &Context<'_>,
+    settings: &LangSettings<'_>,
+) -> HelperResult<Pinned> {
+    let lang = resolve_with(rc, ctx, settings)?;
+    let root = rc
+        .evaluate(settings.lang_path)?
+        .filter(|v| !v.is_null())
+        .cloned();
+    Ok(Pinned { root, lang })
+}
+
+/// Resolve the language identifier using an optional pinned language.
+fn resolve_root(
+    rc: &Render<'_>,
+    ctx: &Context<'_>,
+    settings: &LangSettings<'_>,
+    pinned: Option<&Pinned>,
+) -> HelperResult<LanguageIdentifier> {
+    let lang_path = settings.lang_path;
+    let param = ctx.param(LANG).filter(|v| !v.is_null());
+    let root = match (param, pinned) {
+        (None, None) => rc.evaluate(lang_path)?,
+        _ => None,
+    };
+
+    // At the root the scoped path resolves to the same value
+    let scoped = match (param, scoped_path(lang_path)) {
+        (None, Some(path)) => rc.evaluate(&path)?.filter(|v| {
+            !v.is_null()
+                && match pinned {
+                    Some(pinned) => pinned.root.as_ref() != Some(*v),
+                    None => !root.is_some_and(|root| std::ptr::eq(*v, root)),
+                }
+        }),
+        _ => None,
+    };
+
+    let lang_type = |source: String| FluentError::LangType {
+        helper: ctx.name().to_string(),
+        source,
+    };
+    // Only look for a single locale when the language is missing
+    let default = || {
+        settings
+            .default
+            .or_else(|| single_locale(settings.single_locale))
+    };
+
+    let lang = if let Some(value) = param {
+        to_lang_str(value)
+            .ok_or_else(|| lang_type(format!("'{}' parameter", LANG)))?
+    } else if let Some(value) = scoped {
+        to_lang_str(value).ok_or_else(|| {
+            lang_type(format!("scoped '{}' variable", lang_path))
+        })?
+    } else if let Some(pinned) = pinned {
+        return Ok(pinned.lang.clone());
+    } else if let Some(default) =
+        root.filter(|v| !v.is_null()).map_or_else(default, |_| None)
+    {
+        return Ok(default.clone());
+    } else {
+        let value = root.ok_or_else(|| FluentError::MissingLang {
+            helper: ctx.name().to_string(),
+            lang_path: lang_path.to_string(),
+        })?;
+        to_lang_str(value)
+            .ok_or_else(|| lang_type(format!("'{}' variable", lang_path)))?
+    };
+
+    let alias = settings
+        .aliases
+        .and_then(|aliases| aliases.get(lang.as_ref()));
+    match (alias, settings.accept) {
+        (Some(lang_id), _) => Ok(lang_id.clone()),
+        (None, Some(loader)) if lang.contains(&[',', ';'][..]) => {
+            negotiate(&lang, settings.cache, loader)
+        }
+        (None, _) => settings.cache.parse(&lang),
+    }
+}
+
+/// Get the locale of a loader that has exactly one locale.
+fn single_locale(loader: &dyn Loader) -> Option<&LanguageIdentifier> {
+    let mut locales = loader.locales();
+    match (locales.next(), locales.next()) {
+        (Some(locale), None) => Some(locale),
+        _ => None,
+    }
+}
+
+/// Language pinned by a block.
+pub(crate) struct Pinned {
+    /// Value at the language path in the root data.
+    root: Option<Value>,
+    lang: LanguageIdentifier,
+}
+
+/// Choose a language from a quality ordered list in the
+/// `Accept-Language` format such as `fr-CH, fr;q=0.9, en;q=0.8`.
+///
+/// The first language in order of quality that is a locale of the
+/// loader is chosen, otherwise the locale with the same language
+/// subtag as the first language that has one. When no locale
+/// matches the language with the highest quality is returned so
+/// the loader falls back as usual.
+fn negotiate(
+    list: &str,
+    cache: &LangCache,
+    loader: &dyn Loader,
+) -> HelperResult<LanguageIdentifier> {
+    let mut candidates: Vec<(f32, &str)> = list
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|part| part.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if tag.is_empty() || tag == "*" || quality <= 0.0 {
+                None
+            } else {
+                Some((quality, tag))
+            }
+        })
+        .collect();
+    // Stable so languages with the same quality keep their order
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let languages: Vec<LanguageIdentifier> = candidates
+        .iter()
+        .filter_map(|(_, tag)| cache.parse(tag).ok())
+        .collect();
+    let locales: Vec<&LanguageIdentifier> = loader.locales().collect();
+
+    if let Some(lang_id) =
+        languages.iter().find(|lang_id| locales.contains(lang_id))
+    {
+        return Ok(lang_id.clone());
+    }
+
+    for lang_id in languages.iter() {
+        if let Some(locale) = locales
+            .iter()
+            .find(|locale| locale.language == lang_id.language)
+        {
+            return Ok((*locale).clone());
+        }
+    }
+
+    languages.into_iter().next().ok_or_else(|| {
+        FluentError::Parse(format!("No valid language in the list '{}'", list))
+            .into()
+    })
+}
+
+/// Resolve a `lang` hash parameter that is an index into the
+/// locales of a loader sorted in the same order as the
+/// `FluentLocalesHelper`.
+///
+/// Returns `None` when the parameter is not a number.
+pub(crate) fn from_index(
+    ctx: &Context<'_>,
+    loader: &dyn Loader,
+) -> HelperResult<Option<LanguageIdentifier>> {
+    let index = match ctx.param(LANG) {
+        Some(Value::Number(index)) => index,
+        _ => return Ok(None),
+    };
+
+    let mut locales: Vec<&LanguageIdentifier> = loader.locales().collect();
+    locales.sort_by_key(|lang| lang.to_string());
+
+    index
+        .as_u64()
+        .and_then(|index| locales.get(index as usize))
+        .map(|lang| Some((*lang).clone()))
+        .ok_or_else(|| {
+            HelperError::new(format!(
+                "Helper '{}' language index {} is out of range for {} locales",
+                ctx.name(),
+                index,
+                locales.len()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_templates::ArcLoader;
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use crate::test_util::{fluent, loader};
+    use crate::FluentHelper;
+
+    fn helper() -> FluentHelper {
+        FluentHelper::new(Box::new(loader()))
+    }
+
+    #[test]
+    fn lang_parameter() {
+        let template = r#"{{fluent "welcome" lang="fr"}} {{fluent "welcome"}}"#;
+        let result = fluent(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Bonjour! Hello!");
+    }
+
+    #[test]
+    fn lang_path() {
+        let helper = FluentHelper::with_lang_path(
+            Box::new(loader()),
+            "@root.site.locale",
+        );
+        let data = json!({"site": {"locale": "fr"}});
+        let result = fluent(helper, r#"{{fluent "welcome"}}"#, data);
+        assert_eq!(result.unwrap(), "Bonjour!");
+
+        let helper = FluentHelper::with_lang_path(
+            Box::new(loader()),
+            "@root.site.locale",
+        );
+        let result = fluent(helper, r#"{{fluent "welcome"}}"#, json!({}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("@root.site.locale"));
+    }
+
+    #[test]
+    fn lang_cache() {
+        let cache: LangCache = Default::default();
+        assert_eq!(cache.parse("en-US").unwrap().to_string(), "en-US");
+        assert_eq!(cache.parse("en-US").unwrap().to_string(), "en-US");
+        assert_eq!(cache.entries.read().unwrap().len(), 1);
+        assert!(cache.parse("!").is_err());
+    }
+
+    #[test]
+    fn lang_object() {
+        let data = json!({"lang": {"language": "fr"}});
+        let result = fluent(helper(), r#"{{fluent "welcome"}}"#, data);
+        assert_eq!(result.unwrap(), "Bonjour!");
+
+        let data = json!({"lang": "en", "german": {"language": "de"}});
+        let template = r#"{{fluent "welcome" lang=german}}"#;
+        let result = fluent(helper(), template, data);
+        assert_eq!(result.unwrap(), "Hallo!");
+
+        let data = json!({"lang": {"region": "FR"}});
+        let result = fluent(helper(), r#"{{fluent "welcome"}}"#, data);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("must be a string or language object"));
+    }
+
+    #[test]
+    fn lang_parameter_path() {
+        let data = json!({"lang": "en", "user": {"locale": "de"}});
+        let template = r#"{{fluent "welcome" lang=@root.user.locale}} {{fluent "welcome" lang=user.locale}} {{fluent "welcome"}}"#;
+        let result = fluent(helper(), template, data);
+        assert_eq!(result.unwrap(), "Hallo! Hallo! Hello!");
+    }
+
+    #[test]
+    fn require_known_lang() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .require_known_lang(true)
+            .build();
+        let result =
+            fluent(helper, r#"{{fluent "welcome"}}"#, json!({"lang": "xx"}));
+        assert!(result.unwrap_err().to_string().contains("xx"));
+
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .require_known_lang(true)
+            .build();
+        let result =
+            fluent(helper, r#"{{fluent "welcome"}}"#, json!({"lang": "de"}));
+        assert_eq!(result.unwrap(), "Hallo!");
+    }
+
+    #[test]
+    fn lang_index() {
+        let lang_index = || {
+            FluentHelper::builder(Box::new(loader()))
+                .lang_index(true)
+                .build()
+        };
+        let template =
+            r#"{{fluent "welcome" lang=0}} {{fluent "welcome" lang="fr"}}"#;
+        let result = fluent(lang_index(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hallo! Bonjour!");
+
+        let template = r#"{{fluent "welcome" lang=3}}"#;
+        let result = fluent(lang_index(), template, json!({"lang": "en"}));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("language index 3 is out of range for 3 locales"));
+    }
+
+    #[test]
+    fn scope_lang() {
+        let data = json!({"lang": "en", "french": {"lang": "fr"}, "user": {}});
+        let template = r#"{{#with french}}{{fluent "welcome"}} {{fluent "welcome" lang="de"}}{{/with}} {{#with user}}{{fluent "welcome"}}{{/with}}"#;
+        let result = fluent(helper(), template, data);
+        assert_eq!(result.unwrap(), "Bonjour! Hallo! Hello!");
+    }
+
+    #[test]
+    fn default_lang() {
+        let default_lang = FluentHelper::builder(Box::new(loader()))
+            .default_lang(unic_langid::langid!("fr"))
+            .build();
+        let template = r#"{{fluent "welcome"}} {{fluent "welcome" lang="de"}}"#;
+        let result = fluent(default_lang, template, json!({}));
+        assert_eq!(result.unwrap(), "Bonjour! Hallo!");
+
+        let result = fluent(helper(), r#"{{fluent "welcome"}}"#, json!({}));
+        assert!(result.unwrap_err().to_string().contains("@root.lang"));
+    }
+
+    #[test]
+    fn single_locale() {
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let single = ArcLoader::builder(path, langid!("ar")).build().unwrap();
+        let helper = FluentHelper::new(Box::new(single));
+        let result = fluent(helper, r#"{{fluent "welcome"}}"#, json!({}));
+        assert_eq!(result.unwrap(), "مرحبا");
+    }
+
+    #[test]
+    fn posix_locale() {
+        let helper = || {
+            FluentHelper::builder(Box::new(loader()))
+                .lang_arg("id")
+                .build()
+        };
+        let template = r#"{{fluent "echo"}}"#;
+        let posix = fluent(helper(), template, json!({"lang": "en_US"}));
+        let bcp47 = fluent(helper(), template, json!({"lang": "en-US"}));
+        assert_eq!(posix.unwrap(), "en-US");
+        assert_eq!(bcp47.unwrap(), "en-US");
+    }
+
+    #[test]
+    fn accept_language() {
+        let accept = || {
+            FluentHelper::builder(Box::new(loader()))
+                .accept_language(true)
+                .build()
+        };
+        let template = r#"{{fluent "welcome"}}"#;
+        let data = json!({"lang": "fr-CH, fr;q=0.9, en;q=0.8"});
+        assert_eq!(fluent(accept(), template, data).unwrap(), "Bonjour!");
+        let data = json!({"lang": "it, en;q=0.5, de;q=0.8"});
+        assert_eq!(fluent(accept(), template, data).unwrap(), "Hallo!");
+        let data = json!({"lang": "de"});
+        assert_eq!(fluent(accept(), template, data).unwrap(), "Hallo!");
+    }
+
+    #[test]
+    fn lang_alias() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .lang_alias("french", langid!("fr"))
+            .lang_alias("german", langid!("de"))
+            .build();
+        let template = r#"{{fluent "welcome"}} {{fluent "welcome" lang="german"}} {{fluent "welcome" lang="en"}}"#;
+        let result = fluent(helper, template, json!({"lang": "french"}));
+        assert_eq!(result.unwrap(), "Bonjour! Hallo! Hello!");
+    }
+
+    #[test]
+    fn lang_cache_insert() {
+        let cache: LangCache = Default::default();
+        cache.insert(&langid!("fr-CA"));
+        assert_eq!(cache.entries.read().unwrap().len(), 1);
+        assert_eq!(cache.parse("fr-CA").unwrap(), langid!("fr-CA"));
+        assert_eq!(cache.entries.read().unwrap().len(), 1);
+    }
+}
@@ -2,13 +2,13 @@
 
 //! Helper for [fluent](https://www.projectfluent.org/) language lookup.
 //!
-//! The root data for the template must contain a `lang` field 
+//! The root data for the template must contain a `lang` field
 //! with the identifier of the current language.
 //!
-//! Assuming a fluent language file in `locales/en/main.ftl` and the `lang` 
+//! Assuming a fluent language file in `locales/en/main.ftl` and the `lang`
 //! field is set to `en`, using the contents:
 //!
-//! ```ignore
+//! ```text
 //! welcome = Hello!
 //! greeting = Hello { $name }!
 //! block = { $var1 } { $var2 }
@@ -16,185 +16,148 @@
 //!
 //! We can resolve the message in a template using the fluent helper like this:
 //!
-//! ```ignore
+//! ```text
 //! {{fluent "welcome"}}
 //! ```
 //!
 //! Pass variables using the helper hash parameters:
 //!
-//! ```ignore
+//! ```text
 //! {{fluent "greeting" name="world"}}
 //! ```
 //!
-//! If you need to pass multi-line variables to a message use the `fluentparam` syntax 
+//! If you need to pass multi-line variables to a message use the `fluentparam` syntax
 //! inside a block call:
 //!
-//! ```ignore
+//! ```text
 //! {{#fluent "block"~}}
 //! {{#fluentparam "var1"~}}
-//! This is some multi-line content for 
+//! This is some multi-line content for
 //! the first variable parameter named var1.
 //! {{/fluentparam}}
 //!
 //! {{#fluentparam "var2"}}
-//! Which is continued in another multi-line 
+//! Which is continued in another multi-line
 //! paragraph using the variable named var2.
 //! {{~/fluentparam~}}
 //! {{/fluent}}
 //! ```
 //!
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-
-use bracket::{
-    error::HelperError,
-    helper::{Helper, HelperValue, LocalHelper},
-    parser::ast::Node,
-    render::{Context, Render, Type},
+//! Register the helper with a loader:
+//!
+//! ```
+//! use bracket::Registry;
+//! use bracket_fluent::FluentHelper;
+//! use fluent_templates::ArcLoader;
+//! use serde_json::json;
+//! use unic_langid::langid;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let loader = ArcLoader::builder("examples/locales", langid!("en")).build()?;
+//! let mut registry = Registry::new();
+//! let helper = FluentHelper::new(Box::new(loader));
+//! registry.helpers_mut().insert("fluent", Box::new(helper));
+//!
+//! let data = json!({"lang": "en"});
+//! let result = registry.once("page", r#"{{fluent "welcome"}}"#, &data)?;
+//! assert_eq!(result, "Hello!");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See `FluentHelper` for how the language and the arguments are
+//! resolved, how missing messages are handled and the settings of the
+//! builder.
+//!
+//! The other helpers in this crate share the settings of a fluent helper;
+//! `register_all()` registers the whole family from one fluent helper.
+//! Loaders cannot list messages, attributes, comments or terms so the
+//! helpers that need them require a `Catalog`, which is also a loader:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use bracket::Registry;
+//! use bracket_fluent::{Catalog, FluentHelper};
+//! use serde_json::json;
+//! use unic_langid::langid;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let catalog = Arc::new(Catalog::builder("examples/locales", langid!("en")).build()?);
+//! let helper = FluentHelper::builder(Box::new(Arc::clone(&catalog)))
+//!     .catalog(catalog)
+//!     .build();
+//! let mut registry = Registry::new();
+//! bracket_fluent::register_all(&mut registry, helper);
+//!
+//! let data = json!({"lang": "fr"});
+//! let template = r#"{{#if (has_fluent "welcome")}}{{fluent "welcome"}}{{/if}}"#;
+//! assert_eq!(registry.once("page", template, &data)?, "Bonjour!");
+//! # Ok(())
+//! # }
+//! ```
+mod args;
+mod cache;
+mod capture;
+mod catalog;
+mod error;
+mod escape;
+mod fluent;
+mod fluent_attr;
+mod fluent_attrs;
+mod fluent_comment;
+mod fluent_dir;
+mod fluent_dump;
+mod fluent_each;
+mod fluent_fallback_lang;
+mod fluent_locales;
+mod fluent_resolved_lang;
+mod fluent_set;
+mod fluent_try;
+mod fluent_vars;
+mod fluent_number;
+mod fluent_pin;
+mod fluent_plural;
+mod fluent_raw;
+mod has_fluent;
+mod has_fluent_attr;
+mod inline;
+mod lang;
+mod message;
+mod number;
+mod register;
+mod shared;
+mod suggest;
+#[cfg(test)]
+mod test_util;
+mod validate;
+mod vars;
+
+pub use catalog::{Catalog, CatalogBuilder, CatalogBundle};
+pub use args::{ArgsTransformFn, ConvertFn};
+pub use escape::EscapeMode;
+pub use fluent::{
+    FluentHelper, FluentHelperBuilder, FluentParam, MissObserverFn, MissingFn,
 };
-
-use serde_json::Value;
-
-use fluent_templates::fluent_bundle::FluentValue;
-use fluent_templates::LanguageIdentifier;
-use fluent_templates::Loader;
-
-static FLUENT_PARAM: &str = "fluentparam";
-
-/// Local helper for `{{#fluentparam}}` blocks.
-#[derive(Clone)]
-pub struct FluentParam {
-    parameters: Arc<RwLock<HashMap<String, String>>>,
-}
-
-impl Helper for FluentParam {
-    fn call<'render, 'call>(
-        &self,
-        rc: &mut Render<'render>,
-        ctx: &Context<'call>,
-        template: Option<&'render Node<'render>>,
-    ) -> HelperValue {
-        ctx.arity(1..1)?;
-        ctx.assert_block(template)?;
-
-        let param_name = ctx.try_get(0, &[Type::String])?.as_str().unwrap();
-
-        let node = template.unwrap();
-        let content = rc.buffer(node)?;
-        let mut writer = self.parameters.write().unwrap();
-        writer.insert(param_name.to_string(), content);
-
-        Ok(None)
-    }
-}
-
-impl LocalHelper for FluentParam {}
-
-/// Lookup a language string in the underlying loader.
-pub struct FluentHelper {
-    loader: Box<dyn Loader + Send + Sync>,
-    /// Escape messages, default is `true`.
-    pub escape: bool,
-}
-
-impl FluentHelper {
-    /// Create a new fluent helper.
-    ///
-    /// Messages are resolved using the underlying loader.
-    pub fn new(loader: Box<dyn Loader + Send + Sync>) -> Self {
-        Self {
-            loader,
-            escape: true,
-        }
-    }
-}
-
-impl Helper for FluentHelper {
-    fn call<'render, 'call>(
-        &self,
-        rc: &mut Render<'render>,
-        ctx: &Context<'call>,
-        template: Option<&'render Node<'render>>,
-    ) -> HelperValue {
-        ctx.arity(1..1)?;
-
-        let msg_id = ctx.try_get(0, &[Type::String])?.as_str().unwrap();
-
-        let lang = rc
-            .evaluate("@root.lang")?
-            .ok_or_else(|| {
-                HelperError::new(format!(
-                    "Helper '{}' requires a 'lang' variable in the template data",
-                    ctx.name()
-                ))
-            })?
-            .as_str()
-            .ok_or_else(|| {
-                HelperError::new(format!(
-                    "Type error in helper '{}' the 'lang' variable must be a string",
-                    ctx.name()
-                ))
-            })?;
-
-        let lang_id = lang
-            .parse::<LanguageIdentifier>()
-            .map_err(|e| HelperError::new(e.to_string()))?;
-
-        // Build arguments from hash parameters
-        let mut args: Option<HashMap<String, FluentValue>> =
-            if ctx.parameters().is_empty() {
-                None
-            } else {
-                let map = ctx
-                    .parameters()
-                    .iter()
-                    .filter_map(|(k, v)| {
-                        let val = match v {
-                            // `Number::as_f64` can't fail here because we haven't
-                            // enabled `arbitrary_precision` feature
-                            // in `serde_json`.
-                            Value::Number(n) => n.as_f64().unwrap().into(),
-                            Value::String(s) => s.to_owned().into(),
-                            _ => return None,
-                        };
-                        Some((k.to_string(), val))
-                    })
-                    .collect();
-                Some(map)
-            };
-
-        if let Some(node) = template {
-            let parameters: Arc<RwLock<HashMap<String, String>>> =
-                Arc::new(RwLock::new(HashMap::new()));
-            let local_helper = FluentParam {
-                parameters: Arc::clone(&parameters),
-            };
-            rc.register_local_helper(FLUENT_PARAM, Box::new(local_helper));
-            rc.template(node)?;
-            rc.unregister_local_helper(FLUENT_PARAM);
-
-            let lock = Arc::try_unwrap(parameters).expect(
-                "Fluent helper parameters lock still has multiple owners!",
-            );
-            let map = lock
-                .into_inner()
-                .expect("Fluent helper failed to get inner value from lock!");
-
-            let params = args.get_or_insert(HashMap::new());
-            for (k, v) in map {
-                params.insert(k, FluentValue::String(v.into()));
-            }
-        }
-
-        let message =
-            self.loader
-                .lookup_complete(&lang_id, &msg_id, args.as_ref());
-        if self.escape {
-            rc.write_escaped(&message)?;
-        } else {
-            rc.write(&message)?;
-        }
-
-        Ok(None)
-    }
-}
+pub use fluent_attr::FluentAttrHelper;
+pub use fluent_attrs::FluentAttrsHelper;
+pub use fluent_comment::FluentCommentHelper;
+pub use fluent_dir::FluentDirHelper;
+pub use fluent_dump::FluentDumpHelper;
+pub use fluent_each::FluentEachHelper;
+pub use fluent_fallback_lang::FluentFallbackLangHelper;
+pub use fluent_locales::FluentLocalesHelper;
+pub use fluent_resolved_lang::FluentResolvedLangHelper;
+pub use fluent_set::FluentSetHelper;
+pub use fluent_try::FluentTryHelper;
+pub use fluent_vars::FluentVarsHelper;
+pub use fluent_number::FluentNumberHelper;
+pub use fluent_pin::FluentPinHelper;
+pub use fluent_plural::FluentPluralHelper;
+pub use fluent_raw::FluentRawHelper;
+pub use has_fluent::HasFluentHelper;
+pub use has_fluent_attr::HasFluentAttrHelper;
+pub use number::NumberFormatFn;
+pub use register::{register_all, register_catalog};
+pub use shared::SharedLoader;
+pub use validate::{TemplateProblem, UnknownUsage, UnusedReport};
@@ -0,0 +1,249 @@
+//! Utilities for messages returned by a loader.
+use bracket::{
+    error::HelperError,
+    helper::{HelperResult, HelperValue},
+    parser::ast::Element,
+    render::{Context, Render},
+};
+
+use serde_json::Value;
+
+use crate::{
+    args::{ESCAPE, ON_MISSING},
+    escape::{self, EscapeMode},
+};
+
+/// Prefix of the message returned by the loaders when a
+/// message could not be found.
+static UNKNOWN_LOCALIZATION: &str = "Unknown localization ";
+
+/// Determine if a message returned by the loader indicates
+/// the message id could not be found.
+///
+/// The `Loader` trait does not distinguish missing messages so we
+/// compare against the text the loaders return for a missing message.
+/// A message that formats to an empty string is therefore not missing.
+pub(crate) fn is_missing(message: &str, msg_id: &str) -> bool {
+    message.len() == UNKNOWN_LOCALIZATION.len() + msg_id.len()
+        && message.starts_with(UNKNOWN_LOCALIZATION)
+        && message.ends_with(msg_id)
+}
+
+/// Outcome for a missing message set by the `on_missing`
+/// hash parameter.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum OnMissing {
+    /// Return an error as in strict mode.
+    Error,
+    /// Use an empty string.
+    Empty,
+    /// Use the message id.
+    Id,
+    /// Return `null` from a sub-expression and write nothing.
+    Null,
+    /// Use the handling configured for the helper.
+    Default,
+}
+
+/// Get the outcome for a missing message from the hash parameters.
+pub(crate) fn on_missing(ctx: &Context<'_>) -> HelperResult<OnMissing> {
+    let value = match ctx.param(ON_MISSING) {
+        Some(value) => value,
+        None => return Ok(OnMissing::Default),
+    };
+    match value.as_str() {
+        Some("error") => Ok(OnMissing::Error),
+        Some("empty") => Ok(OnMissing::Empty),
+        Some("id") => Ok(OnMissing::Id),
+        Some("null") => Ok(OnMissing::Null),
+        Some("default") => Ok(OnMissing::Default),
+        _ => Err(HelperError::new(format!(
+            "Type error in helper '{}' the '{}' parameter must be one of 'error', 'empty', 'id', 'null' or 'default'",
+            ctx.name(),
+            ON_MISSING
+        ))),
+    }
+}
+
+/// Get the text the loaders return for a missing message.
+pub(crate) fn unknown(msg_id: &str) -> String {
+    format!("{}{}", UNKNOWN_LOCALIZATION, msg_id)
+}
+
+/// Remove the unicode isolation marks that fluent inserts
+/// around placeables.
+pub(crate) fn strip_isolating(message: String) -> String {
+    if message.contains(&['\u{2068}', '\u{2069}'][..]) {
+        message.replace(&['\u{2068}', '\u{2069}'][..], "")
+    } else {
+        message
+    }
+}
+
+/// Remove leading and trailing whitespace from a message.
+pub(crate) fn trim(message: String) -> String {
+    let trimmed = message.trim();
+    if trimmed.len() == message.len() {
+        message
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Determine if a helper was invoked as a sub-expression.
+pub(crate) fn is_sub_expression(ctx: &Context<'_>) -> bool {
+    ctx.call().open().starts_with('(')
+}
+
+/// Write a resolved message.
+///
+/// Sub-expressions return the message without writing to the output
+/// so that it can be passed to other helpers or partials, otherwise the
+/// message is written to the output and escaped using the escape mode
+/// unless the call uses the triple-stash syntax or the `escape` hash
+/// parameter or the `escape` setting of the helper is `false`. The `Html`
+/// escape mode uses the escape function of the registry so disabling
+/// escaping in the registry also applies; when the registry uses the
+/// default escape function plain messages are written without escaping.
+pub(crate) fn write(
+    rc: &mut Render<'_>,
+    ctx: &Context<'_>,
+    message: String,
+    escape: bool,
+    mode: EscapeMode,
+) -> HelperValue {
+    if is_sub_expression(ctx) {
+        return Ok(Some(Value::String(message)));
+    }
+
+    // Triple-stash calls are not escaped, same as variables
+    let escape = if !ctx.call().is_escaped() {
+        false
+    } else if let Some(value) = ctx.param(ESCAPE) {
+        value.as_bool().ok_or_else(|| {
+            HelperError::new(format!(
+                "Type error in helper '{}' the 'escape' parameter must be a boolean",
+                ctx.name()
+            ))
+        })?
+    } else {
+        escape
+    };
+
+    if escape {
+        match mode {
+            EscapeMode::Html
+                if escape::is_html_plain(&message)
+                    && escape::is_default(rc.registry().escape()) =>
+            {
+                rc.write(&message)?
+            }
+            EscapeMode::Html => rc.write_escaped(&message)?,
+            EscapeMode::Json => rc.write(&escape::json(&message))?,
+            EscapeMode::None => rc.write(&message)?,
+            EscapeMode::Custom(escape) => rc.write(&(escape)(&message))?,
+        };
+    } else {
+        rc.write(&message)?;
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::EscapeMode;
+    use bracket::Registry;
+    use serde_json::json;
+
+    use crate::test_util::{fluent, loader};
+    use crate::FluentHelper;
+
+    fn render(template: &str) -> bracket::Result<String> {
+        let helper = FluentHelper::new(Box::new(loader()));
+        fluent(helper, template, json!({"lang": "en"}))
+    }
+
+    #[test]
+    fn escape_parameter() {
+        let template =
+            r#"{{fluent "raw-html" escape=false}} {{fluent "raw-html"}}"#;
+        assert_eq!(
+            render(template).unwrap(),
+            "<b>Bold</b> &lt;b&gt;Bold&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn sub_expression() {
+        let template = r#"{{#if (fluent "welcome")}}yes{{/if}} {{json (fluent "welcome")}}"#;
+        assert_eq!(render(template).unwrap(), "yes &quot;Hello!&quot;");
+    }
+
+    #[test]
+    fn default_text() {
+        let template = r#"{{fluent "nope" default="N/A"}} {{fluent "welcome" default="N/A"}}"#;
+        assert_eq!(render(template).unwrap(), "N/A Hello!");
+
+        let mut helper = FluentHelper::new(Box::new(loader()));
+        helper.missing =
+            Some(Box::new(|msg_id, lang| format!("[{}:{}]", lang, msg_id)));
+        let result =
+            fluent(helper, r#"{{fluent "nope"}}"#, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "[fr:nope]");
+    }
+
+    #[test]
+    fn strict() {
+        let mut helper = FluentHelper::new(Box::new(loader()));
+        helper.strict = true;
+        let result =
+            fluent(helper, r#"{{fluent "nope"}}"#, json!({"lang": "en"}));
+        assert!(result.unwrap_err().to_string().contains("nope"));
+    }
+
+    #[test]
+    fn escape_mode() {
+        let escape = |mode: EscapeMode| {
+            let helper = FluentHelper::builder(Box::new(loader()))
+                .escape_mode(mode)
+                .build();
+            let data = json!({"lang": "en", "name": "\"<Ann>\"\n"});
+            fluent(helper, r#"{{fluent "greeting" name=name}}"#, data).unwrap()
+        };
+        assert_eq!(
+            escape(EscapeMode::Html),
+            "Hello &quot;&lt;Ann&gt;&quot;\n!"
+        );
+        assert_eq!(escape(EscapeMode::Json), r#"Hello \"<Ann>\"\n!"#);
+        assert_eq!(escape(EscapeMode::None), "Hello \"<Ann>\"\n!");
+    }
+
+    #[test]
+    fn partial_argument() {
+        let mut registry = Registry::new();
+        let helper = FluentHelper::new(Box::new(loader()));
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        registry.insert("card", "<h1>{{title}}</h1>").unwrap();
+        let data = json!({"lang": "en"});
+        let template = r#"{{> card title=(fluent "raw-html")}}"#;
+        let result = registry.once("test", template, &data);
+        assert_eq!(result.unwrap(), "<h1>&lt;b&gt;Bold&lt;/b&gt;</h1>");
+    }
+
+    #[test]
+    fn on_missing() {
+        let template = r#"[{{fluent "nope" on_missing="empty"}}] [{{fluent "nope" on_missing="id"}}] [{{#if (fluent "nope" on_missing="null")}}yes{{else}}no{{/if}}] [{{fluent "nope" on_missing="default" default="N/A"}}]"#;
+        assert_eq!(render(template).unwrap(), "[] [nope] [no] [N/A]");
+
+        let result = render(r#"{{fluent "nope" on_missing="error"}}"#);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("could not find message 'nope'"));
+        let result = render(r#"{{fluent "welcome" on_missing="error"}}"#);
+        assert_eq!(result.unwrap(), "Hello!");
+        let result = render(r#"{{fluent "nope" on_missing="skip"}}"#);
+        assert!(result.unwrap_err().to_string().contains("must be one of"));
+    }
+}
@@ -0,0 +1,565 @@
+//! Locale aware number formatting.
+use std::collections::HashMap;
+
+use bracket::{error::HelperError, helper::HelperResult, render::Context};
+
+use serde_json::{Map, Value};
+
+use fluent_templates::fluent_bundle::types::{
+    FluentNumber, FluentNumberOptions, FluentNumberStyle,
+};
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::LanguageIdentifier;
+
+/// Type for functions that format numbers for a language.
+///
+/// The function receives the number with the formatting options
+/// and the language identifier; return `None` to use the default
+/// formatting.
+pub type NumberFormatFn = Box<
+    dyn Fn(&FluentNumber, &LanguageIdentifier) -> Option<String> + Send + Sync,
+>;
+
+/// Separator between an argument name and a number option.
+static OPTION_SEPARATOR: &str = "__";
+
+/// Option that passes a number argument as an unformatted string.
+static RAW: &str = "raw";
+
+/// Get the number formatting options from the hash parameters.
+///
+/// Supports the `style`, `currency`, `useGrouping`,
+/// `minimumFractionDigits` and `maximumFractionDigits` parameters.
+pub(crate) fn options(ctx: &Context<'_>) -> HelperResult<FluentNumberOptions> {
+    let mut options: FluentNumberOptions = Default::default();
+    for (key, value) in ctx.parameters() {
+        set_option(&mut options, key, value)
+            .map_err(|_| digits_error(ctx, key))?;
+    }
+    Ok(options)
+}
+
+/// Set a number option, returns `false` when the name is not a
+/// number option and an error when a fraction digits option is not
+/// a positive integer.
+fn set_option(
+    options: &mut FluentNumberOptions,
+    name: &str,
+    value: &Value,
+) -> Result<bool, ()> {
+    match (name, value) {
+        ("style", Value::String(style)) => {
+            options.style = style.as_str().into();
+        }
+        ("currency", Value::String(currency)) => {
+            options.currency = Some(currency.to_string());
+        }
+        ("useGrouping", Value::Bool(grouping)) => {
+            options.use_grouping = *grouping;
+        }
+        ("minimumFractionDigits", Value::Number(n)) => {
+            options.minimum_fraction_digits = Some(digits(n)?);
+        }
+        ("maximumFractionDigits", Value::Number(n)) => {
+            options.maximum_fraction_digits = Some(digits(n)?);
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Format number arguments using options from the hash parameters.
+///
+/// Hash parameters named after an argument with a `__` suffix
+/// followed by a number option (for example `amount__style`) are
+/// removed from the arguments and the number argument is replaced
+/// with the number formatted for the language.
+///
+/// The `raw` option (for example `version__raw=true`) replaces the
+/// number with an unformatted string and takes precedence over the
+/// other options.
+///
+/// The custom function is also used for the other number arguments;
+/// a number it formats is passed to the message as a string.
+pub(crate) fn format_arguments(
+    ctx: &Context<'_>,
+    args: &mut HashMap<String, FluentValue<'static>>,
+    lang: &LanguageIdentifier,
+    custom: Option<&NumberFormatFn>,
+) -> HelperResult<()> {
+    let mut arguments: HashMap<&str, FluentNumberOptions> = HashMap::new();
+    let mut raw: Vec<&str> = Vec::new();
+    for (key, value) in ctx.parameters() {
+        if let Some(pos) = key.rfind(OPTION_SEPARATOR) {
+            let (arg, name) = key.split_at(pos);
+            let name = &name[OPTION_SEPARATOR.len()..];
+            if arg.is_empty() {
+                continue;
+            }
+            if name == RAW {
+                let enabled = value.as_bool().ok_or_else(|| {
+                    HelperError::new(format!(
+                        "Type error in helper '{}' the '{}' parameter must be a boolean",
+                        ctx.name(),
+                        key
+                    ))
+                })?;
+                args.remove(key.as_str());
+                if enabled {
+                    raw.push(arg);
+                }
+                continue;
+            }
+            let mut options = arguments.get(arg).cloned().unwrap_or_default();
+            if set_option(&mut options, name, value)
+                .map_err(|_| digits_error(ctx, key))?
+            {
+                args.remove(key.as_str());
+                arguments.insert(arg, options);
+            }
+        }
+    }
+
+    for arg in raw {
+        arguments.remove(arg);
+        match args.get(arg) {
+            Some(FluentValue::Number(number)) => {
+                let value = number.value.to_string();
+                args.insert(arg.to_string(), value.into());
+            }
+            Some(_) => log::warn!(
+                "Helper '{}' raw option for argument '{}' which is not a number",
+                ctx.name(),
+                arg
+            ),
+            None => {}
+        }
+    }
+
+    for (arg, options) in arguments {
+        match args.get(arg) {
+            Some(FluentValue::Number(number)) => {
+                let number = FluentNumber::new(number.value, options);
+                let value = format_with(&number, lang, custom);
+                args.insert(arg.to_string(), value.into());
+            }
+            Some(_) => log::warn!(
+                "Helper '{}' number options for argument '{}' which is not a number",
+                ctx.name(),
+                arg
+            ),
+            None => {}
+        }
+    }
+
+    if let Some(custom) = custom {
+        for value in args.values_mut() {
+            if let FluentValue::Number(number) = value {
+                if let Some(formatted) = (custom)(number, lang) {
+                    *value = formatted.into();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn digits(n: &serde_json::Number) -> Result<usize, ()> {
+    n.as_u64().map(|n| n as usize).ok_or(())
+}
+
+fn digits_error(ctx: &Context<'_>, key: &str) -> HelperError {
+    HelperError::new(format!(
+        "Type error in helper '{}' the '{}' parameter must be a positive integer",
+        ctx.name(),
+        key
+    ))
+}
+
+/// Key that marks an object as a tagged fluent value.
+static TAG: &str = "$fluent";
+static TAG_NUMBER: &str = "number";
+static TAG_VALUE: &str = "value";
+static TAG_OPTIONS: &str = "options";
+
+/// Convert a number to a tagged object so it can be passed from
+/// a sub-expression to a message as a number.
+///
+/// The object has the form
+/// `{"$fluent": "number", "value": 42, "options": {...}}` where the
+/// options use the same names as the hash parameters.
+pub(crate) fn to_tagged(number: &FluentNumber) -> Value {
+    let options = &number.options;
+    let mut fields = Map::new();
+    let style = match options.style {
+        FluentNumberStyle::Decimal => "decimal",
+        FluentNumberStyle::Currency => "currency",
+        FluentNumberStyle::Percent => "percent",
+    };
+    fields.insert("style".to_string(), Value::from(style));
+    if let Some(ref currency) = options.currency {
+        fields.insert("currency".to_string(), Value::from(currency.as_str()));
+    }
+    fields.insert("useGrouping".to_string(), Value::from(options.use_grouping));
+    if let Some(digits) = options.minimum_fraction_digits {
+        fields.insert("minimumFractionDigits".to_string(), Value::from(digits));
+    }
+    if let Some(digits) = options.maximum_fraction_digits {
+        fields.insert("maximumFractionDigits".to_string(), Value::from(digits));
+    }
+
+    let mut tagged = Map::new();
+    tagged.insert(TAG.to_string(), Value::from(TAG_NUMBER));
+    tagged.insert(TAG_VALUE.to_string(), Value::from(number.value));
+    tagged.insert(TAG_OPTIONS.to_string(), Value::Object(fields));
+    Value::Object(tagged)
+}
+
+/// Convert a tagged object to a fluent value.
+///
+/// Returns `None` when the value is not a tagged object; invalid
+/// options are skipped.
+pub(crate) fn from_tagged(value: &Value) -> Option<FluentValue<'static>> {
+    let fields = value.as_object()?;
+    if fields.get(TAG)?.as_str()? != TAG_NUMBER {
+        return None;
+    }
+    let value = fields.get(TAG_VALUE)?.as_f64()?;
+    let mut options: FluentNumberOptions = Default::default();
+    if let Some(Value::Object(members)) = fields.get(TAG_OPTIONS) {
+        for (name, value) in members {
+            if let Err(()) = set_option(&mut options, name, value) {
+                log::warn!("Skipping invalid tagged number option '{}'", name);
+            }
+        }
+    }
+    Some(FluentValue::Number(FluentNumber::new(value, options)))
+}
+
+/// Format a number for a language using a custom function
+/// before the default formatting.
+pub(crate) fn format_with(
+    number: &FluentNumber,
+    lang: &LanguageIdentifier,
+    custom: Option<&NumberFormatFn>,
+) -> String {
+    custom
+        .and_then(|custom| (custom)(number, lang))
+        .unwrap_or_else(|| format(number, lang))
+}
+
+/// Separators and symbol positions for a language.
+struct Symbols {
+    /// Separator between groups of thousands.
+    group: &'static str,
+    /// Separator before the fraction digits.
+    decimal: &'static str,
+    /// Written between the number and a percent sign or a currency
+    /// symbol that follows the number.
+    space: &'static str,
+    /// Whether a currency symbol follows the number.
+    currency_after: bool,
+}
+
+static EN: Symbols = Symbols {
+    group: ",",
+    decimal: ".",
+    space: "",
+    currency_after: false,
+};
+
+static FR: Symbols = Symbols {
+    group: "\u{202f}",
+    decimal: ",",
+    space: "\u{202f}",
+    currency_after: true,
+};
+
+static DE: Symbols = Symbols {
+    group: ".",
+    decimal: ",",
+    space: "\u{a0}",
+    currency_after: true,
+};
+
+static RU: Symbols = Symbols {
+    group: "\u{a0}",
+    decimal: ",",
+    space: "\u{a0}",
+    currency_after: true,
+};
+
+/// Get the symbols for a language, languages that are not
+/// listed use the English symbols.
+fn symbols(lang: &LanguageIdentifier) -> &'static Symbols {
+    match lang.language.as_str() {
+        "fr" => &FR,
+        "de" | "es" | "it" | "pt" | "da" => &DE,
+        "ru" | "uk" | "pl" | "cs" | "sv" | "fi" | "nb" => &RU,
+        _ => &EN,
+    }
+}
+
+/// Get the symbol for a currency code, codes without
+/// a known symbol are written as the code.
+fn currency_symbol(code: &str) -> &str {
+    match code {
+        "EUR" => "€",
+        "USD" => "$",
+        "GBP" => "£",
+        "JPY" => "¥",
+        _ => code,
+    }
+}
+
+/// Get the default fraction digits for a currency code.
+fn currency_digits(code: &str) -> usize {
+    match code {
+        "JPY" | "KRW" => 0,
+        _ => 2,
+    }
+}
+
+/// Format a number for a language.
+///
+/// The number is rounded to the `maximumFractionDigits` and padded
+/// to the `minimumFractionDigits`, the integer digits are grouped
+/// in thousands unless `useGrouping` is `false` and the group and
+/// decimal separators are those of the language. The `percent` style
+/// multiplies the number by one hundred and defaults to no fraction
+/// digits; the `currency` style with a `currency` code writes the
+/// currency symbol and defaults to the fraction digits of the currency.
+/// Negative zero is formatted as zero.
+///
+/// Only a small table of languages is known, other languages use
+/// the English separators so a `number_format` function should be
+/// used for complete locale support.
+pub(crate) fn format(
+    number: &FluentNumber,
+    lang: &LanguageIdentifier,
+) -> String {
+    if !number.value.is_finite() {
+        return number.as_string().to_string();
+    }
+
+    let options = &number.options;
+    let symbols = symbols(lang);
+    let mut value = number.value;
+    let mut min = options.minimum_fraction_digits;
+    let mut max = options.maximum_fraction_digits;
+
+    let currency = match options.style {
+        FluentNumberStyle::Currency => options.currency.as_deref(),
+        _ => None,
+    };
+    if let Some(code) = currency {
+        let digits = currency_digits(code);
+        min = min.or(Some(digits));
+        max = max.or_else(|| min.map(|min| min.max(digits)));
+    } else if options.style == FluentNumberStyle::Percent {
+        value *= 100.0;
+        max = max.or_else(|| min.or(Some(0)));
+    }
+
+    let min = min.unwrap_or(0);
+    let mut digits = match max {
+        Some(max) => {
+            let digits = format!("{:.*}", max.max(min), value.abs());
+            match digits.find('.') {
+                Some(pos) => {
+                    let keep =
+                        digits.trim_end_matches('0').len().max(pos + 1 + min);
+                    digits[..keep].trim_end_matches('.').to_string()
+                }
+                None => digits,
+            }
+        }
+        None => value.abs().to_string(),
+    };
+    let fraction = digits.find('.').map(|pos| digits.len() - pos - 1);
+    match fraction {
+        Some(fraction) if fraction < min => {
+            digits.push_str(&"0".repeat(min - fraction))
+        }
+        None if min > 0 => {
+            digits.push('.');
+            digits.push_str(&"0".repeat(min));
+        }
+        _ => {}
+    }
+
+    let (integer, fraction) = match digits.find('.') {
+        Some(pos) => (&digits[..pos], Some(&digits[pos + 1..])),
+        None => (&digits[..], None),
+    };
+    let mut formatted = if options.use_grouping {
+        group(integer, symbols.group)
+    } else {
+        integer.to_string()
+    };
+    if let Some(fraction) = fraction {
+        formatted.push_str(symbols.decimal);
+        formatted.push_str(fraction);
+    }
+
+    let negative = value < 0.0 && digits.bytes().any(|b| b > b'0');
+    let sign = if negative { "-" } else { "" };
+    match (currency, &options.style) {
+        (Some(code), _) => {
+            let symbol = currency_symbol(code);
+            if symbols.currency_after {
+                format!("{}{}{}{}", sign, formatted, symbols.space, symbol)
+            } else if symbol == code {
+                format!("{}{}\u{a0}{}", sign, symbol, formatted)
+            } else {
+                format!("{}{}{}", sign, symbol, formatted)
+            }
+        }
+        (None, FluentNumberStyle::Percent) => {
+            format!("{}{}{}%", sign, formatted, symbols.space)
+        }
+        _ => format!("{}{}", sign, formatted),
+    }
+}
+
+/// Insert a separator between groups of three integer digits.
+fn group(integer: &str, separator: &str) -> String {
+    let mut output = String::with_capacity(integer.len() * 2);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            output.push_str(separator);
+        }
+        output.push(c);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{fluent, loader};
+    use crate::FluentHelper;
+
+    fn number(value: f64, options: Value) -> FluentNumber {
+        let mut number: FluentNumberOptions = Default::default();
+        for (name, value) in options.as_object().unwrap() {
+            assert!(set_option(&mut number, name, value).unwrap());
+        }
+        FluentNumber::new(value, number)
+    }
+
+    #[test]
+    fn grouping() {
+        let (en, fr) = (langid!("en"), langid!("fr"));
+        let grouped = number(1234567.89, json!({}));
+        assert_eq!(format(&grouped, &en), "1,234,567.89");
+        assert_eq!(format(&grouped, &fr), "1\u{202f}234\u{202f}567,89");
+        assert_eq!(format(&grouped, &langid!("de")), "1.234.567,89");
+        let plain = number(1234567.89, json!({"useGrouping": false}));
+        assert_eq!(format(&plain, &en), "1234567.89");
+        assert_eq!(format(&plain, &fr), "1234567,89");
+        assert_eq!(format(&number(-123.0, json!({})), &en), "-123");
+        assert_eq!(format(&number(-1234.0, json!({})), &en), "-1,234");
+    }
+
+    #[test]
+    fn fraction_digits() {
+        let en = langid!("en");
+        let max = number(1.5, json!({"maximumFractionDigits": 0}));
+        assert_eq!(format(&max, &en), "2");
+        let max = number(2.50, json!({"maximumFractionDigits": 3}));
+        assert_eq!(format(&max, &en), "2.5");
+        let min = number(2.5, json!({"minimumFractionDigits": 3}));
+        assert_eq!(format(&min, &en), "2.500");
+        let both = number(
+            2.0,
+            json!({"minimumFractionDigits": 1, "maximumFractionDigits": 3}),
+        );
+        assert_eq!(format(&both, &en), "2.0");
+        let zero = number(-0.001, json!({"maximumFractionDigits": 2}));
+        assert_eq!(format(&zero, &en), "0");
+    }
+
+    #[test]
+    fn styles() {
+        let (en, fr) = (langid!("en"), langid!("fr"));
+        let percent = number(0.25, json!({"style": "percent"}));
+        assert_eq!(format(&percent, &en), "25%");
+        assert_eq!(format(&percent, &fr), "25\u{202f}%");
+        let euros =
+            number(1234.5, json!({"style": "currency", "currency": "EUR"}));
+        assert_eq!(format(&euros, &en), "€1,234.50");
+        assert_eq!(format(&euros, &fr), "1\u{202f}234,50\u{202f}€");
+        let francs =
+            number(-42.0, json!({"style": "currency", "currency": "CHF"}));
+        assert_eq!(format(&francs, &en), "-CHF\u{a0}42.00");
+        let yen =
+            number(1234.0, json!({"style": "currency", "currency": "JPY"}));
+        assert_eq!(format(&yen, &en), "¥1,234");
+        let code = number(42.0, json!({"currency": "EUR"}));
+        assert_eq!(format(&code, &en), "42");
+    }
+
+    #[test]
+    fn currency_options() {
+        let currency = || {
+            FluentHelper::builder(Box::new(loader()))
+                .number_format(Box::new(|number, lang| {
+                    let currency = number.options.currency.as_ref()?;
+                    if number.options.style != FluentNumberStyle::Currency {
+                        return None;
+                    }
+                    let digits = number.options.minimum_fraction_digits?;
+                    let value = format!("{:.*}", digits, number.value);
+                    Some(match lang.language.as_str() {
+                        "fr" => {
+                            format!("{} {}", value.replace('.', ","), currency)
+                        }
+                        _ => format!("{} {}", currency, value),
+                    })
+                }))
+                .build()
+        };
+        let template = r#"{{fluent "price" amount=42 amount__style="currency" amount__currency="EUR" amount__minimumFractionDigits=2}}"#;
+        let result = fluent(currency(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Price: EUR 42.00");
+        let result = fluent(currency(), template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Prix : 42,00 EUR");
+    }
+
+    #[test]
+    fn currency_default() {
+        let helper = || FluentHelper::new(Box::new(loader()));
+        let template = r#"{{fluent "price" amount=42 amount__style="currency" amount__currency="EUR"}}"#;
+        let result = fluent(helper(), template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Price: €42.00");
+        let result = fluent(helper(), template, json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Prix : 42,00\u{202f}€");
+    }
+
+    #[test]
+    fn raw_option() {
+        let helper = FluentHelper::builder(Box::new(loader()))
+            .number_format(Box::new(|number, _| {
+                let digits = format!("{}", number.value as u64);
+                let (head, tail) = digits.split_at(digits.len() % 3);
+                let mut groups: Vec<&str> = tail
+                    .as_bytes()
+                    .chunks(3)
+                    .map(|chunk| std::str::from_utf8(chunk).unwrap())
+                    .collect();
+                if !head.is_empty() {
+                    groups.insert(0, head);
+                }
+                Some(groups.join(","))
+            }))
+            .build();
+        let template = r#"{{fluent "echo" id=1234 id__useGrouping=true}} {{fluent "echo" id=1234 id__useGrouping=true id__raw=true}}"#;
+        let result = fluent(helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "1,234 1234");
+    }
+}
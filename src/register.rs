@@ -0,0 +1,207 @@
+//! Register the family of helpers in one call.
+use std::sync::Arc;
+
+use bracket::{
+    helper::{Helper, HelperValue},
+    parser::ast::Node,
+    registry::Registry,
+    render::{Context, Render},
+};
+
+use crate::{
+    catalog::Catalog, fluent::FluentHelper, fluent_attr::FluentAttrHelper,
+    fluent_attrs::FluentAttrsHelper, fluent_comment::FluentCommentHelper,
+    fluent_dir::FluentDirHelper, fluent_dump::FluentDumpHelper,
+    fluent_each::FluentEachHelper,
+    fluent_fallback_lang::FluentFallbackLangHelper,
+    fluent_locales::FluentLocalesHelper, fluent_number::FluentNumberHelper,
+    fluent_pin::FluentPinHelper, fluent_plural::FluentPluralHelper,
+    fluent_raw::FluentRawHelper,
+    fluent_resolved_lang::FluentResolvedLangHelper,
+    fluent_set::FluentSetHelper, fluent_try::FluentTryHelper,
+    fluent_vars::FluentVarsHelper, has_fluent::HasFluentHelper,
+    has_fluent_attr::HasFluentAttrHelper,
+};
+
+/// Fluent helper registered with the other helpers.
+struct Shared {
+    helper: Arc<FluentHelper>,
+}
+
+impl Helper for Shared {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        self.helper.call(rc, ctx, template)
+    }
+}
+
+/// Register all the helpers using one fluent helper.
+///
+/// Registers `fluent`, `fluent_raw`, `fluent_each`, `fluent_set`,
+/// `fluent_pin`, `fluent_plural`, `fluent_locales`, `fluent_number` and
+/// `fluent_dir`;
+/// when the fluent helper has a catalog the `has_fluent`, `fluent_attr`,
+/// `fluent_attrs`, `has_fluent_attr`, `fluent_vars`, `fluent_comment`,
+/// `fluent_dump`, `fluent_fallback_lang`, `fluent_resolved_lang` and
+/// `fluent_try` helpers are also registered.
+///
+/// Every helper shares the settings and the loader of the fluent helper
+/// so reloading the loader applies to all of them.
+pub fn register_all<H: Into<Arc<FluentHelper>>>(
+    registry: &mut Registry<'_>,
+    helper: H,
+) {
+    let helper = helper.into();
+    let helpers = registry.helpers_mut();
+    helpers.insert(
+        "fluent",
+        Box::new(Shared {
+            helper: Arc::clone(&helper),
+        }),
+    );
+    helpers.insert(
+        "fluent_raw",
+        Box::new(FluentRawHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_each",
+        Box::new(FluentEachHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_set",
+        Box::new(FluentSetHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_pin",
+        Box::new(FluentPinHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_plural",
+        Box::new(FluentPluralHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_locales",
+        Box::new(FluentLocalesHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_number",
+        Box::new(FluentNumberHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_dir",
+        Box::new(FluentDirHelper::new(Arc::clone(&helper))),
+    );
+
+    let fallback = match helper.catalog {
+        Some(ref catalog) => catalog.fallback().clone(),
+        None => return,
+    };
+    helpers.insert(
+        "has_fluent",
+        Box::new(HasFluentHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_attr",
+        Box::new(FluentAttrHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_attrs",
+        Box::new(FluentAttrsHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "has_fluent_attr",
+        Box::new(HasFluentAttrHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_vars",
+        Box::new(FluentVarsHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_comment",
+        Box::new(FluentCommentHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_dump",
+        Box::new(FluentDumpHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert(
+        "fluent_fallback_lang",
+        Box::new(FluentFallbackLangHelper::new(fallback)),
+    );
+    helpers.insert(
+        "fluent_resolved_lang",
+        Box::new(FluentResolvedLangHelper::new(Arc::clone(&helper))),
+    );
+    helpers.insert("fluent_try", Box::new(FluentTryHelper::new(helper)));
+}
+
+/// Register all the helpers using a catalog with the default settings.
+///
+/// The catalog is used as the loader and assigned to the `catalog` of
+/// the fluent helper; to change the settings call `register_all()`
+/// with a fluent helper that has a catalog.
+pub fn register_catalog(registry: &mut Registry<'_>, catalog: Arc<Catalog>) {
+    let helper = FluentHelper::builder(Box::new(Arc::clone(&catalog)))
+        .catalog(catalog)
+        .build();
+    register_all(registry, helper);
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket::Registry;
+    use fluent_templates::ArcLoader;
+    use serde_json::json;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::test_util::{catalog, helper};
+
+    #[test]
+    fn shared_settings() {
+        let helper = FluentHelper::builder(Box::new(catalog()))
+            .catalog(catalog())
+            .id_prefix("log")
+            .build();
+        let helper = Arc::new(helper);
+        let mut registry = Registry::new();
+        register_all(&mut registry, Arc::clone(&helper));
+        let template = r#"{{fluent "in"}} {{fluent_attr "in" "title"}} {{has_fluent "in"}}"#;
+        let result = registry.once("test", template, &json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Log in Click to log in true");
+    }
+
+    #[test]
+    fn reload() {
+        let helper = Arc::new(helper());
+        let mut registry = Registry::new();
+        register_all(&mut registry, Arc::clone(&helper));
+        let template = r#"{{fluent_plural "items" 1}}"#;
+        let result = registry.once("test", template, &json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Un article");
+
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let loader = ArcLoader::builder(path, langid!("ar")).build().unwrap();
+        helper.reload(Box::new(loader));
+        let template = r#"{{#each (fluent_locales)}}{{this}}{{/each}}"#;
+        let result = registry.once("test", template, &json!({}));
+        assert_eq!(result.unwrap(), "ar");
+        let result = registry.once("test", "{{fluent_dir}}", &json!({}));
+        assert_eq!(result.unwrap(), "rtl");
+    }
+
+    #[test]
+    fn register_with_catalog() {
+        let mut registry = Registry::new();
+        register_catalog(&mut registry, catalog());
+        let template =
+            r#"{{fluent_attr "login" "title"}} {{has_fluent "welcome"}}"#;
+        let result = registry.once("test", template, &json!({"lang": "fr"}));
+        assert_eq!(result.unwrap(), "Cliquez pour vous connecter true");
+    }
+}
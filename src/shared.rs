@@ -0,0 +1,83 @@
+//! Loader that can be replaced at runtime.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use fluent_templates::Loader;
+
+/// Loader shared with a helper that can be replaced between renders.
+///
+/// Cloning the shared loader returns a handle to the same loader
+/// so a handle can be kept to reload the loader after the helper
+/// has been registered.
+#[derive(Clone)]
+pub struct SharedLoader {
+    inner: Arc<RwLock<Arc<dyn Loader + Send + Sync>>>,
+    generation: Arc<AtomicUsize>,
+}
+
+impl SharedLoader {
+    /// Create a new shared loader.
+    pub fn new(loader: Box<dyn Loader + Send + Sync>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Arc::from(loader))),
+            generation: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Replace the loader.
+    ///
+    /// Calls that are in progress continue to use the previous loader.
+    pub fn reload(&self, loader: Box<dyn Loader + Send + Sync>) {
+        let loader = Arc::from(loader);
+        match self.inner.write() {
+            Ok(mut current) => *current = loader,
+            Err(poisoned) => *poisoned.into_inner() = loader,
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Get the number of times the loader has been replaced.
+    pub(crate) fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Get the current loader.
+    ///
+    /// The lock is only held long enough to clone the reference
+    /// so reading does not block a reload for the duration of a call.
+    pub(crate) fn get(&self) -> Arc<dyn Loader + Send + Sync> {
+        match self.inner.read() {
+            Ok(current) => Arc::clone(&current),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_util::loader, FluentHelper};
+    use bracket::Registry;
+    use fluent_templates::ArcLoader;
+    use serde_json::json;
+    use unic_langid::langid;
+
+    #[test]
+    fn reload() {
+        let helper = FluentHelper::new(Box::new(loader()));
+        let shared = helper.shared_loader();
+        let mut registry = Registry::new();
+        registry.helpers_mut().insert("fluent", Box::new(helper));
+        let data = json!({"lang": "en"});
+        let result = registry.once("test", r#"{{fluent "welcome"}}"#, &data);
+        assert_eq!(result.unwrap(), "Hello!");
+        assert_eq!(shared.generation(), 0);
+
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+        let loader = ArcLoader::builder(path, langid!("ar")).build().unwrap();
+        shared.reload(Box::new(loader));
+        assert_eq!(shared.generation(), 1);
+        let result = registry.once("test", r#"{{fluent "welcome"}}"#, &data);
+        assert_eq!(result.unwrap(), "مرحبا");
+    }
+}
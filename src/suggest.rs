@@ -0,0 +1,72 @@
+//! Suggest message ids similar to a missing message id.
+
+/// Maximum number of suggestions.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Compute the Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Find the candidates that are closest to a message id.
+///
+/// Candidates are sorted by distance and only candidates within
+/// a third of the length of the message id (minimum two edits)
+/// are returned.
+pub(crate) fn suggestions<'a, I>(msg_id: &str, candidates: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit = (msg_id.chars().count() / 3).max(2);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|id| (distance(msg_id, id), id))
+        .filter(|(d, _)| *d <= limit)
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, id)| id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{fluent, helper};
+
+    #[test]
+    fn closest() {
+        let candidates = vec!["welcome", "greeting", "farewell"];
+        assert_eq!(suggestions("welcom", candidates.clone()), vec!["welcome"]);
+        assert!(suggestions("zzz", candidates).is_empty());
+    }
+
+    #[test]
+    fn strict_error() {
+        let mut helper = helper();
+        helper.strict = true;
+        let result =
+            fluent(helper, r#"{{fluent "welcom"}}"#, json!({"lang": "en"}));
+        assert!(result.unwrap_err().to_string().contains("welcome"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Fixtures shared by the unit tests.
+use std::{path::PathBuf, sync::Arc};
+
+use bracket::{helper::Helper, Registry};
+use fluent_templates::ArcLoader;
+use serde_json::Value;
+use unic_langid::{langid, LanguageIdentifier};
+
+use crate::{catalog::Catalog, FluentHelper};
+
+/// Directory of the fixture locales.
+pub(crate) static LOCALES: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/locales");
+
+/// Directory of the fixture locales with a single language.
+pub(crate) static SINGLE: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/single");
+
+/// Fallback language of the fixture loaders.
+pub(crate) static EN: LanguageIdentifier = langid!("en");
+
+/// Resource shared by every fixture locale.
+fn core() -> PathBuf {
+    PathBuf::from(LOCALES).join("core.ftl")
+}
+
+/// Loader for the fixture locales without isolation marks.
+pub(crate) fn loader() -> ArcLoader {
+    ArcLoader::builder(LOCALES, EN.clone())
+        .shared_resources(Some(&[core()]))
+        .customize(|bundle| bundle.set_use_isolating(false))
+        .build()
+        .unwrap()
+}
+
+/// Catalog for the fixture locales without isolation marks.
+pub(crate) fn catalog() -> Arc<Catalog> {
+    let shared = [core()];
+    let catalog = Catalog::builder(LOCALES, EN.clone())
+        .shared_resources(Some(&shared))
+        .customize(|bundle| bundle.set_use_isolating(false))
+        .build()
+        .unwrap();
+    Arc::new(catalog)
+}
+
+/// Fluent helper with a catalog that inserts isolation marks
+/// and the `isolating` setting disabled.
+pub(crate) fn stripped() -> FluentHelper {
+    let shared = [core()];
+    let catalog = Catalog::builder(LOCALES, EN.clone())
+        .shared_resources(Some(&shared))
+        .build()
+        .unwrap();
+    FluentHelper::builder(Box::new(loader()))
+        .catalog(Arc::new(catalog))
+        .isolating(false)
+        .build()
+}
+
+/// Fluent helper for the fixture loader with the fixture catalog.
+pub(crate) fn helper() -> FluentHelper {
+    FluentHelper::builder(Box::new(loader()))
+        .catalog(catalog())
+        .build()
+}
+
+/// Fluent helper with the fixture catalog and a loader for the
+/// single language fixture registered as the `single` namespace.
+pub(crate) fn with_namespace() -> FluentHelper {
+    let single = ArcLoader::builder(SINGLE, langid!("ar")).build().unwrap();
+    FluentHelper::builder(Box::new(loader()))
+        .catalog(catalog())
+        .namespace("single", Box::new(single))
+        .build()
+}
+
+/// Render a template with a single helper.
+pub(crate) fn render<H: Helper + 'static>(
+    name: &'static str,
+    helper: H,
+    template: &str,
+    data: Value,
+) -> bracket::Result<String> {
+    let mut registry = Registry::new();
+    registry.helpers_mut().insert(name, Box::new(helper));
+    registry.once("test", template, &data)
+}
+
+/// Render a template with a fluent helper registered as `fluent`.
+pub(crate) fn fluent(
+    helper: FluentHelper,
+    template: &str,
+    data: Value,
+) -> bracket::Result<String> {
+    render("fluent", helper, template, data)
+}
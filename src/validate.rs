@@ -0,0 +1,103 @@
+//! Find message ids used by templates.
+use bracket::parser::ast::{Call, Lines, Node, ParameterValue, Slice};
+
+use serde_json::Value;
+
+use fluent_templates::LanguageIdentifier;
+
+/// Message id that could not be found when validating templates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateProblem {
+    /// Name of the template.
+    pub template: String,
+    /// Line number of the call, starting at one.
+    pub line: usize,
+    /// The message id.
+    pub msg_id: String,
+    /// Language that does not have the message.
+    pub lang: LanguageIdentifier,
+}
+
+/// Message ids of a catalog that are not used by templates.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UnusedReport {
+    /// Message ids that are not used by any template, sorted.
+    pub unused: Vec<String>,
+    /// Calls with a message id that is not a literal so their
+    /// usage is unknown.
+    pub unknown_usage: Vec<UnknownUsage>,
+}
+
+/// Call with a message id that cannot be analyzed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownUsage {
+    /// Name of the template.
+    pub template: String,
+    /// Line number of the call, starting at one.
+    pub line: usize,
+}
+
+/// Message id used by a call, `None` when the argument
+/// is not a literal.
+pub(crate) struct Usage<'a> {
+    pub line: usize,
+    pub msg_id: Option<&'a str>,
+}
+
+/// Collect the message ids of calls to a helper.
+///
+/// Calls with the `inline` hash parameter are ignored.
+pub(crate) fn usages<'a>(
+    node: &'a Node<'a>,
+    name: &str,
+    inline: &str,
+    found: &mut Vec<Usage<'a>>,
+) {
+    match node {
+        Node::Document(ref doc) => {
+            for child in doc.nodes() {
+                usages(child, name, inline, found);
+            }
+        }
+        Node::Statement(ref call) => call_usages(call, name, inline, found),
+        Node::Block(ref block) => {
+            call_usages(block.call(), name, inline, found);
+            for child in block.nodes().iter().chain(block.conditions()) {
+                usages(child, name, inline, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn call_usages<'a>(
+    call: &'a Call<'a>,
+    name: &str,
+    inline: &str,
+    found: &mut Vec<Usage<'a>>,
+) {
+    if call.target().as_str() == name && !call.parameters().contains_key(inline)
+    {
+        let msg_id = match call.arguments().first() {
+            Some(ParameterValue::Json {
+                value: Value::String(msg_id),
+                ..
+            }) => Some(Some(msg_id.as_str())),
+            Some(_) => Some(None),
+            None => None,
+        };
+        if let Some(msg_id) = msg_id {
+            found.push(Usage {
+                line: call.lines().start + 1,
+                msg_id,
+            });
+        }
+    }
+
+    // Calls may also be sub-expressions of arguments and parameters
+    for value in call.arguments().iter().chain(call.parameters().values()) {
+        if let ParameterValue::SubExpr(ref call) = value {
+            call_usages(call, name, inline, found);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Find the variables referenced by a pattern.
+use std::collections::HashMap;
+
+use fluent_syntax::ast::{
+    CallArguments, Expression, InlineExpression, Pattern, PatternElement,
+};
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::LanguageIdentifier;
+
+/// Log a warning for each variable referenced by a message
+/// that is not in the arguments.
+///
+/// Called when formatting the pattern of a message produced errors
+/// so the pattern is the one that was resolved.
+pub(crate) fn warn_missing(
+    pattern: &Pattern<'_>,
+    lang: &LanguageIdentifier,
+    msg_id: &str,
+    args: Option<&HashMap<String, FluentValue>>,
+) {
+    for name in variables(pattern) {
+        if !args.is_some_and(|args| args.contains_key(name)) {
+            log::warn!(
+                "Message '{}' for language '{}' references variable '{}' which was not supplied",
+                msg_id,
+                lang,
+                name
+            );
+        }
+    }
+}
+
+/// Get the names of the variables referenced by a pattern.
+///
+/// Variables are returned in the order they are first referenced
+/// and each name is only included once; variables referenced by
+/// other messages and terms are not included.
+pub(crate) fn variables<'a>(pattern: &'a Pattern<'a>) -> Vec<&'a str> {
+    let mut names = Vec::new();
+    pattern_variables(pattern, &mut names);
+    names
+}
+
+/// Add the names of the variables referenced by a pattern
+/// that are not already in the list.
+pub(crate) fn pattern_variables<'a>(
+    pattern: &'a Pattern<'a>,
+    names: &mut Vec<&'a str>,
+) {
+    for element in pattern.elements.iter() {
+        if let PatternElement::Placeable(expression) = element {
+            expression_variables(expression, names);
+        }
+    }
+}
+
+fn expression_variables<'a>(
+    expression: &'a Expression<'a>,
+    names: &mut Vec<&'a str>,
+) {
+    match expression {
+        Expression::InlineExpression(inline) => inline_variables(inline, names),
+        Expression::SelectExpression { selector, variants } => {
+            inline_variables(selector, names);
+            for variant in variants {
+                pattern_variables(&variant.value, names);
+            }
+        }
+    }
+}
+
+fn inline_variables<'a>(
+    inline: &'a InlineExpression<'a>,
+    names: &mut Vec<&'a str>,
+) {
+    match inline {
+        InlineExpression::VariableReference { id }
+            if !names.contains(&id.name) =>
+        {
+            names.push(id.name);
+        }
+        InlineExpression::FunctionReference {
+            arguments: Some(arguments),
+            ..
+        }
+        | InlineExpression::TermReference {
+            arguments: Some(arguments),
+            ..
+        } => arguments_variables(arguments, names),
+        InlineExpression::Placeable { expression } => {
+            expression_variables(expression, names)
+        }
+        _ => {}
+    }
+}
+
+fn arguments_variables<'a>(
+    arguments: &'a CallArguments<'a>,
+    names: &mut Vec<&'a str>,
+) {
+    for inline in arguments.positional.iter() {
+        inline_variables(inline, names);
+    }
+    for named in arguments.named.iter() {
+        inline_variables(&named.value, names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::sync::Once;
+
+    use log::{Level, LevelFilter, Log, Metadata, Record};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{catalog, render, EN};
+    use crate::FluentHelper;
+
+    thread_local! {
+        static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Logger that keeps the warnings logged by the current thread.
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.level() <= Level::Warn
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                let message = record.args().to_string();
+                WARNINGS.with(|warnings| warnings.borrow_mut().push(message));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture() {
+        static INIT: Once = Once::new();
+        static LOGGER: Capture = Capture;
+        INIT.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(LevelFilter::Warn);
+        });
+        WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+    }
+
+    fn warnings() -> Vec<String> {
+        WARNINGS.with(|warnings| warnings.borrow().clone())
+    }
+
+    #[test]
+    fn warn_missing_variable() {
+        capture();
+        let helper = FluentHelper::new(Box::new(catalog()));
+        let template =
+            r#"{{fluent "greeting"}}|{{fluent "greeting" name="Ann"}}"#;
+        let result = render("fluent", helper, template, json!({"lang": "en"}));
+        assert_eq!(result.unwrap(), "Hello {$name}!|Hello Ann!");
+        let warnings: Vec<String> = warnings()
+            .into_iter()
+            .filter(|warning| warning.contains("references variable"))
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'greeting'"));
+        assert!(warnings[0].contains("'name'"));
+    }
+
+    #[test]
+    fn message_variables() {
+        let catalog = catalog();
+        let (_, pattern) = catalog.message_value(&EN, "receipt").unwrap();
+        assert_eq!(variables(pattern), vec!["name", "count", "currency"]);
+    }
+}
@@ -0,0 +1,2 @@
+block = { $var1 } { $var2 }
+-brand-name = Acme
@@ -0,0 +1,3 @@
+welcome = Hallo!
+greeting = Hallo { $name }!
+farewell = Auf Wiedersehen!
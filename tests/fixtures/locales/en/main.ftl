@@ -0,0 +1,31 @@
+# Shown on the home page
+welcome = Hello!
+greeting = Hello { $name }!
+farewell = Goodbye!
+help = Help
+login = Log in
+    .title = Click to log in
+items = { $count ->
+    [one] One item
+   *[other] { $count } items
+}
+card = Card: { $body }
+status = { $active ->
+    [true] Active
+   *[false] Inactive
+}
+list-items = Items: { $items }
+echo = { $id }
+raw-html = <b>Bold</b>
+receipt = { $name } bought { $count } { $currency }
+addr = { $user-name }, { $user-city }
+signup = Welcome { $name }
+    .hint = Sent to { $email }
+price = Price: { $amount }
+locale-note = { $lang ->
+    [fr] French
+   *[other] Other
+}
+padded = {"  "}Padded{"  "}
+empty = {""}
+broken = Made by { -missing-term }
@@ -0,0 +1,9 @@
+welcome = Bonjour!
+greeting = Bonjour { $name }!
+login = Connexion
+    .title = Cliquez pour vous connecter
+items = { $count ->
+    [one] Un article
+   *[other] { $count } articles
+}
+price = Prix : { $amount }
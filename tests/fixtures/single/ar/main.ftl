@@ -0,0 +1,2 @@
+welcome = مرحبا
+farewell = وداعا